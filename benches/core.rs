@@ -0,0 +1,127 @@
+//! Benchmarks for the core on its own, without a window or any sleeping:
+//! getting instructions decoded and run, drawing sprites, and whole frames
+//! of the games in `tests/games`, each on both backends (the `_blocks`
+//! ones use blocks). `cargo bench` runs them, and criterion compares each
+//! run with the last.
+
+#[path = "../tests/games/mod.rs"]
+mod games;
+
+use chip8_emulator::blocks::Backend;
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::movie::Movie;
+use chip8_emulator::timing::Timing;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+// a loop through most of the instructions that do not draw
+const DECODE: [u8; 26] = [
+    0x60, 0x05, // 200: V0 = 5
+    0x71, 0x01, // 202: V1 += 1
+    0x82, 0x14, // 204: V2 += V1
+    0x83, 0x26, // 206: V3 = V2 >> 1
+    0xa3, 0x00, // 208: I = $300
+    0xf0, 0x1e, // 20a: I += V0
+    0x30, 0x06, // 20c: skip if V0 == 6
+    0x40, 0x06, // 20e: skip if V0 != 6
+    0x50, 0x10, // 210: skip if V0 == V1
+    0xf4, 0x07, // 212: V4 = delay
+    0x22, 0x18, // 214: call $218
+    0x12, 0x00, // 216: jump to $200
+    0x00, 0xee, // 218: return
+];
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(1000));
+
+    group.bench_function("step", |b| {
+        let mut chip8 = Chip8State::builder().rom(&DECODE).build();
+        b.iter(|| {
+            for _ in 0..1000 {
+                chip8.step().unwrap();
+            }
+        });
+    });
+    group.bench_function("fetch", |b| {
+        let chip8 = Chip8State::builder().rom(&DECODE).build();
+        b.iter(|| {
+            for _ in 0..1000 {
+                black_box(black_box(&chip8).fetch().unwrap());
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn sprites(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dxyn");
+    let sprite = [0xff; 15];
+
+    // lined up with a byte, across two bytes, off the right edge, and in high resolution
+    for (name, x, hires) in [("aligned", 8, false), ("unaligned", 13, false), ("clipped", 60, false), ("hires", 13, true)] {
+        group.bench_function(name, |b| {
+            let mut builder = Chip8State::builder().i(0x300).memory(0x300, &sprite).v(0, x).v(1, 10);
+            if hires {
+                builder = builder.hires();
+            }
+            let mut chip8 = builder.build();
+            b.iter(|| {
+                chip8.pc = 0x200;
+                chip8.execute(black_box(0xd01f)).unwrap();
+            });
+        });
+    }
+
+    group.finish();
+}
+
+// the first `frames` frames of a game, played the way its movie does
+fn play(rom: &[u8], movie: &str, timing: Timing, backend: Backend, frames: u64) {
+    let movie = Movie::parse(movie).unwrap();
+    let mut chip8 = Chip8State::builder().rom(rom).seed(movie.seed).build();
+    chip8.timing = timing;
+    chip8.backend = backend;
+    chip8.movie = Some(movie);
+
+    for _ in 0..frames {
+        chip8.set_keys([false; 16]);
+        chip8.run_frame().unwrap();
+    }
+    black_box(chip8.screen.checksum());
+}
+
+fn frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame");
+    group.throughput(Throughput::Elements(60));
+
+    let recorded = [
+        ("snake", games::SNAKE, include_str!("../tests/games/snake.movie")),
+        ("pong", games::PONG, include_str!("../tests/games/pong.movie")),
+        ("breakout", games::BREAKOUT, include_str!("../tests/games/breakout.movie")),
+    ];
+    for (backend, suffix) in [(Backend::Interpreter, ""), (Backend::Blocks, "_blocks")] {
+        for (name, rom, movie) in recorded {
+            group.bench_function(format!("{}{}", name, suffix), |b| b.iter(|| play(rom, movie, Timing::Ipf(30), backend, 60)));
+        }
+        group.bench_function(format!("pong_vip{}", suffix), |b| {
+            b.iter(|| play(games::PONG, recorded[1].2, Timing::Vip, backend, 60))
+        });
+        // as many instructions as a frame will take, a busy loop never waits on the timers
+        group.bench_function(format!("decode_1000_ipf{}", suffix), |b| {
+            let mut chip8 = Chip8State::builder().rom(&DECODE).build();
+            chip8.timing = Timing::Ipf(1000);
+            chip8.backend = backend;
+            b.iter(|| {
+                for _ in 0..60 {
+                    chip8.run_frame().unwrap();
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, decode, sprites, frames);
+criterion_main!(benches);
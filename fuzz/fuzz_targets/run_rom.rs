@@ -0,0 +1,39 @@
+#![no_main]
+
+use chip8_emulator::blocks::Backend;
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::coverage::Coverage;
+use chip8_emulator::profile::Profiler;
+use chip8_emulator::timing::Timing;
+use chip8_emulator::watch::Watcher;
+use libfuzzer_sys::fuzz_target;
+
+// the first byte picks the keys held down, the timing and the backend, the rest is the program
+fuzz_target!(|data: &[u8]| {
+    let Some((&setup, rom)) = data.split_first() else {
+        return;
+    };
+
+    let mut chip8 = Chip8State::builder().build();
+    if chip8.load_rom(rom).is_err() {
+        return;
+    }
+    chip8.timing = if setup & 0x80 == 0 {Timing::Ipf(100)} else {Timing::Vip};
+    // the instruments need every instruction, so the blocks backend only runs without them
+    if setup & 0x40 == 0 {
+        chip8.profiler = Some(Profiler::new(100, "instructions"));
+        chip8.coverage = Some(Coverage::default());
+        chip8.watcher = Some(Watcher::new(false));
+    } else {
+        chip8.backend = Backend::Blocks;
+    }
+
+    for frame in 0..60 {
+        let key = (setup as usize + frame) & 0xf;
+        chip8.set_keys(std::array::from_fn(|k| k == key && frame % 3 != 0));
+        // a fault is fine, a panic is what this is looking for
+        if chip8.run_frame().is_err() || chip8.halted() {
+            return;
+        }
+    }
+});
@@ -0,0 +1,109 @@
+use crate::blocks::Backend;
+use crate::chip8::{Chip8State, Fault};
+use crate::framebuffer::Framebuffer;
+use crate::movie::Movie;
+use crate::timing::Timing;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// One machine for `run` to play through, headless and as fast as it goes.
+#[derive(Clone, Debug)]
+pub struct Job {
+    // what the outcome is reported under, usually the file the rom came from
+    pub name: String,
+    pub rom: Vec<u8>,
+    pub seed: u64,
+    pub frames: u64,
+    pub timing: Timing,
+    pub backend: Backend,
+    pub display_wait: bool,
+    // keys to play back, the movie's seed replaces the job's; no keys at all without one
+    pub movie: Option<Movie>,
+}
+
+impl Job {
+    /// A job with the same defaults as the command line: 600 frames at 10
+    /// instructions each, seed 0 and nobody at the keys.
+    pub fn new(name: &str, rom: &[u8]) -> Self {
+        Job {
+            name: name.to_string(),
+            rom: rom.to_vec(),
+            seed: 0,
+            frames: 600,
+            timing: Timing::Ipf(10),
+            backend: Backend::default(),
+            display_wait: false,
+            movie: None,
+        }
+    }
+
+    /// Plays the job through to the end of its frames, the first fault, or
+    /// the program running off its end.
+    pub fn run(&self) -> Outcome {
+        let mut chip8 = Chip8State::new([0; 4096], Framebuffer::new(), [0; 16], 0);
+        chip8.timing = self.timing;
+        chip8.backend = self.backend;
+        chip8.display_wait = self.display_wait;
+        chip8.seed(self.seed);
+        if let Some(movie) = &self.movie {
+            chip8.seed(movie.seed);
+            chip8.movie = Some(movie.clone());
+        }
+
+        let mut fault = chip8.load_rom(&self.rom).err();
+        while fault.is_none() && chip8.frame < self.frames && !chip8.halted() {
+            chip8.set_keys([false; 16]);
+            fault = chip8.run_frame().err();
+        }
+
+        Outcome {name: self.name.clone(), fault, chip8}
+    }
+}
+
+/// How a job ended, with the machine as it was left.
+pub struct Outcome {
+    pub name: String,
+    pub fault: Option<Fault>,
+    pub chip8: Chip8State,
+}
+
+impl Outcome {
+    pub fn halted(&self) -> bool {
+        self.fault.is_none() && self.chip8.halted()
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.chip8.frame
+    }
+
+    pub fn screen_hash(&self) -> u64 {
+        self.chip8.screen.checksum()
+    }
+}
+
+/// Runs the jobs on up to `threads` threads, each taking the next job that
+/// is left, and gives back their outcomes in the same order as the jobs.
+pub fn run(jobs: &[Job], threads: usize) -> Vec<Outcome> {
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::with_capacity(jobs.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let at = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(job) = jobs.get(at) else {
+                        break;
+                    };
+                    let outcome = job.run();
+                    outcomes.lock().unwrap().push((at, outcome));
+                }
+            });
+        }
+    });
+
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by_key(|(at, _)| *at);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
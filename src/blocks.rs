@@ -0,0 +1,190 @@
+use crate::chip8::{Chip8State, Fault};
+use crate::instruction::Instruction;
+use std::ops::Range;
+
+/// How `run_frame` gets through the instructions. Both behave exactly the
+/// same, blocks skip the per-instruction decode and dispatch.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    // one instruction at a time, from the decoded instruction cache
+    #[default]
+    Interpreter,
+    // straight-line runs compiled once into a function per instruction
+    Blocks,
+}
+
+impl Backend {
+    pub fn named(name: &str) -> Option<Backend> {
+        match name {
+            "interpreter" => Some(Backend::Interpreter),
+            "blocks" => Some(Backend::Blocks),
+            _ => None,
+        }
+    }
+}
+
+// long enough for any loop body, short enough that a frame's budget rarely cuts one off
+const MAX_LENGTH: usize = 64;
+// translations kept before starting over, so code that keeps rewriting itself cannot pile them up
+const MAX_OPS: usize = 1 << 16;
+
+type Handler = fn(&mut Chip8State, u16) -> Result<(), Fault>;
+
+/// One instruction compiled for its block: a function that does only what
+/// this instruction does, with the operands left in the opcode, so running
+/// it is a call instead of a decode and a match on every instruction.
+#[derive(Clone, Copy)]
+pub(crate) struct Op {
+    pub run: Handler,
+    // kept for the VIP timing, and for the handler to take its operands from
+    pub opcode: u16,
+}
+
+/// Compiled blocks by the address they start at. A block runs from there up
+/// to and including the first instruction that could do anything but carry
+/// on to the next: a jump, skip, call or return, a draw or key wait that can
+/// pause the frame, or a write that can change code. As writes end a block,
+/// nothing can change a block while it runs. A write to memory drops every
+/// block it touches, so code that rewrites itself is compiled again.
+#[derive(Default)]
+pub(crate) struct Blocks {
+    // the ops of every block one after another, a block being a range of them
+    ops: Vec<Op>,
+    // left empty until the first block, the interpreter never needs them
+    at: Vec<Option<Range<u32>>>,
+    // addresses inside a block, so writes elsewhere cost nothing
+    covered: Vec<bool>,
+}
+
+impl Blocks {
+    /// Where the ops of the block starting at `pc` are, compiling it the
+    /// first time. The block stops before `end`. None when not even one
+    /// instruction fits, for the interpreter to deal with.
+    #[inline(always)]
+    pub fn block(&mut self, memory: &[u8], pc: u16, end: u16) -> Option<Range<usize>> {
+        match self.at.get(pc as usize) {
+            Some(Some(ops)) => Some(ops.start as usize..ops.end as usize),
+            _ => self.compile(memory, pc, end),
+        }
+    }
+
+    #[inline(always)]
+    pub fn op(&self, index: usize) -> Op {
+        self.ops[index]
+    }
+
+    #[cold]
+    fn compile(&mut self, memory: &[u8], pc: u16, end: u16) -> Option<Range<usize>> {
+        if self.at.is_empty() || self.ops.len() >= MAX_OPS {
+            self.ops.clear();
+            self.at = vec![None; memory.len()];
+            self.covered = vec![false; memory.len()];
+        }
+
+        let start = pc as usize;
+        let end = (end as usize).min(memory.len());
+        let first = self.ops.len();
+        let mut address = start;
+        while address < end && address + 2 <= memory.len() && self.ops.len() - first < MAX_LENGTH {
+            let opcode = u16::from_be_bytes([memory[address], memory[address + 1]]);
+            let instruction = Instruction::decode(opcode);
+            self.ops.push(Op {run: handler(instruction), opcode});
+            address += 2;
+            if ends_block(instruction) {
+                break;
+            }
+        }
+        if address == start {
+            return None;
+        }
+
+        self.covered[start..address].fill(true);
+        self.at[start] = Some(first as u32..self.ops.len() as u32);
+        Some(first..self.ops.len())
+    }
+
+    /// Drops the blocks that `addresses` were part of.
+    pub fn forget(&mut self, addresses: Range<usize>) {
+        if self.covered.is_empty() || !self.covered[addresses.clone()].contains(&true) {
+            return;
+        }
+
+        for (start, block) in self.at.iter_mut().enumerate() {
+            let overlaps = block.as_ref().is_some_and(|ops| {
+                start < addresses.end && addresses.start < start + 2 * ops.len()
+            });
+            if overlaps {
+                *block = None;
+            }
+        }
+        // the blocks left do not cover these, the ones dropped may leave marks elsewhere
+        self.covered[addresses].fill(false);
+    }
+}
+
+fn ends_block(instruction: Instruction) -> bool {
+    matches!(instruction,
+        Instruction::Return | Instruction::Jump(_) | Instruction::Call(_) | Instruction::JumpV0(_)
+        | Instruction::SkipEqual {..} | Instruction::SkipNotEqual {..}
+        | Instruction::SkipEqualRegister {..} | Instruction::SkipNotEqualRegister {..}
+        | Instruction::SkipKey {..} | Instruction::SkipNotKey {..}
+        | Instruction::Draw {..} | Instruction::WaitKey {..}
+        | Instruction::Decimal {..} | Instruction::Store {..})
+}
+
+fn x(opcode: u16) -> u8 {
+    (opcode >> 8 & 0xf) as u8
+}
+
+fn y(opcode: u16) -> u8 {
+    (opcode >> 4 & 0xf) as u8
+}
+
+fn nn(opcode: u16) -> u8 {
+    opcode as u8
+}
+
+/// A function for each kind of instruction. Each builds its instruction
+/// from the opcode and hands it to `Chip8State::run`, which is inlined, so
+/// the match there folds away to just that instruction's arm.
+fn handler(instruction: Instruction) -> Handler {
+    match instruction {
+        Instruction::Clear => |chip8, _| chip8.run(Instruction::Clear),
+        Instruction::Return => |chip8, _| chip8.run(Instruction::Return),
+        Instruction::Lores => |chip8, _| chip8.run(Instruction::Lores),
+        Instruction::Hires => |chip8, _| chip8.run(Instruction::Hires),
+        Instruction::Jump(_) => |chip8, opcode| chip8.run(Instruction::Jump(opcode & 0xfff)),
+        Instruction::Call(_) => |chip8, opcode| chip8.run(Instruction::Call(opcode & 0xfff)),
+        Instruction::SkipEqual {..} => |chip8, opcode| chip8.run(Instruction::SkipEqual {x: x(opcode), nn: nn(opcode)}),
+        Instruction::SkipNotEqual {..} => |chip8, opcode| chip8.run(Instruction::SkipNotEqual {x: x(opcode), nn: nn(opcode)}),
+        Instruction::SkipEqualRegister {..} => |chip8, opcode| chip8.run(Instruction::SkipEqualRegister {x: x(opcode), y: y(opcode)}),
+        Instruction::SkipNotEqualRegister {..} => |chip8, opcode| chip8.run(Instruction::SkipNotEqualRegister {x: x(opcode), y: y(opcode)}),
+        Instruction::Set {..} => |chip8, opcode| chip8.run(Instruction::Set {x: x(opcode), nn: nn(opcode)}),
+        Instruction::Add {..} => |chip8, opcode| chip8.run(Instruction::Add {x: x(opcode), nn: nn(opcode)}),
+        Instruction::Move {..} => |chip8, opcode| chip8.run(Instruction::Move {x: x(opcode), y: y(opcode)}),
+        Instruction::Or {..} => |chip8, opcode| chip8.run(Instruction::Or {x: x(opcode), y: y(opcode)}),
+        Instruction::And {..} => |chip8, opcode| chip8.run(Instruction::And {x: x(opcode), y: y(opcode)}),
+        Instruction::Xor {..} => |chip8, opcode| chip8.run(Instruction::Xor {x: x(opcode), y: y(opcode)}),
+        Instruction::AddRegister {..} => |chip8, opcode| chip8.run(Instruction::AddRegister {x: x(opcode), y: y(opcode)}),
+        Instruction::Subtract {..} => |chip8, opcode| chip8.run(Instruction::Subtract {x: x(opcode), y: y(opcode)}),
+        Instruction::ShiftRight {..} => |chip8, opcode| chip8.run(Instruction::ShiftRight {x: x(opcode), y: y(opcode)}),
+        Instruction::SubtractFrom {..} => |chip8, opcode| chip8.run(Instruction::SubtractFrom {x: x(opcode), y: y(opcode)}),
+        Instruction::ShiftLeft {..} => |chip8, opcode| chip8.run(Instruction::ShiftLeft {x: x(opcode), y: y(opcode)}),
+        Instruction::SetI(_) => |chip8, opcode| chip8.run(Instruction::SetI(opcode & 0xfff)),
+        Instruction::JumpV0(_) => |chip8, opcode| chip8.run(Instruction::JumpV0(opcode & 0xfff)),
+        Instruction::Random {..} => |chip8, opcode| chip8.run(Instruction::Random {x: x(opcode), nn: nn(opcode)}),
+        Instruction::Draw {..} => |chip8, opcode| chip8.run(Instruction::Draw {x: x(opcode), y: y(opcode), n: (opcode & 0xf) as u8}),
+        Instruction::SkipKey {..} => |chip8, opcode| chip8.run(Instruction::SkipKey {x: x(opcode)}),
+        Instruction::SkipNotKey {..} => |chip8, opcode| chip8.run(Instruction::SkipNotKey {x: x(opcode)}),
+        Instruction::GetDelay {..} => |chip8, opcode| chip8.run(Instruction::GetDelay {x: x(opcode)}),
+        Instruction::WaitKey {..} => |chip8, opcode| chip8.run(Instruction::WaitKey {x: x(opcode)}),
+        Instruction::SetDelay {..} => |chip8, opcode| chip8.run(Instruction::SetDelay {x: x(opcode)}),
+        Instruction::SetSound {..} => |_, _| Ok(()),
+        Instruction::AddI {..} => |chip8, opcode| chip8.run(Instruction::AddI {x: x(opcode)}),
+        Instruction::Font {..} => |chip8, opcode| chip8.run(Instruction::Font {x: x(opcode)}),
+        Instruction::Decimal {..} => |chip8, opcode| chip8.run(Instruction::Decimal {x: x(opcode)}),
+        Instruction::Store {..} => |chip8, opcode| chip8.run(Instruction::Store {x: x(opcode)}),
+        Instruction::Load {..} => |chip8, opcode| chip8.run(Instruction::Load {x: x(opcode)}),
+        Instruction::Unknown(_) => |_, _| Ok(()),
+    }
+}
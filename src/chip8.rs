@@ -0,0 +1,680 @@
+use crate::blocks::{Backend, Blocks};
+use crate::coverage::{Access, Coverage};
+use crate::framebuffer::Framebuffer;
+use crate::instruction::Instruction;
+use crate::movie::Movie;
+use crate::profile::Profiler;
+use crate::timing::{self, Timing};
+use crate::trace::{Registers, Step, Tracer};
+use crate::watch::Watcher;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+pub const FONT: [u8; 0x50] = [
+    0xf0, 0x90, 0x90, 0x90, 0xf0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xf0, 0x10, 0xf0, 0x80, 0xf0, // 2
+    0xf0, 0x10, 0xf0, 0x10, 0xf0, // 3
+    0x90, 0x90, 0xf0, 0x10, 0x10, // 4
+    0xf0, 0x80, 0xf0, 0x10, 0xf0, // 5
+    0xf0, 0x80, 0xf0, 0x90, 0xf0, // 6
+    0xf0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xf0, 0x90, 0xf0, 0x90, 0xf0, // 8
+    0xf0, 0x90, 0xf0, 0x10, 0xf0, // 9
+    0xf0, 0x90, 0xf0, 0x90, 0x90, // A
+    0xe0, 0x90, 0xe0, 0x90, 0xe0, // B
+    0xf0, 0x80, 0x80, 0x80, 0xf0, // C
+    0xe0, 0x90, 0x90, 0x90, 0xe0, // D
+    0xf0, 0x80, 0xf0, 0x80, 0xf0, // E
+    0xf0, 0x80, 0xf0, 0x80, 0x80, // F
+];
+
+// how deep calls can nest, as on most interpreters after the VIP's 12
+pub const STACK_SIZE: usize = 16;
+
+/// Something a program did that no chip-8 could carry out. The machine is left
+/// as it was before the instruction, with `pc` pointing at it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fault {
+    // 00EE with nothing to return to
+    StackUnderflow {pc: u16},
+    // 2NNN with the stack already full, usually endless recursion
+    StackOverflow {pc: u16},
+    // an instruction fetch or data access past the end of memory
+    MemoryOutOfRange {pc: u16, address: usize},
+    // EX9E or EXA1 with a value in VX that is not a key
+    InvalidKey {pc: u16, key: u8},
+    // the program does not fit between 0x200 and the end of memory
+    RomTooLarge {size: usize},
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Fault::StackUnderflow {pc} => write!(f, "${:03x}: return without a call", pc),
+            Fault::StackOverflow {pc} => write!(f, "${:03x}: more than {} nested calls", pc, STACK_SIZE),
+            Fault::MemoryOutOfRange {pc, address} => write!(f, "${:03x}: address ${:x} is past the end of memory", pc, address),
+            Fault::InvalidKey {pc, key} => write!(f, "${:03x}: there is no key {:x}", pc, key),
+            Fault::RomTooLarge {size} => write!(f, "the program is {} bytes, only {} fit", size, 4096 - 0x200),
+        }
+    }
+}
+
+impl Error for Fault {}
+
+impl From<Fault> for io::Error {
+    fn from(fault: Fault) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, fault)
+    }
+}
+
+pub struct Chip8State {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub delay: u8,
+    pub font: [u8; 0x50],
+    // only write_memory changes it, as that also forgets what was decoded there
+    memory: [u8; 1024 * 4],
+    // instructions already decoded, by address
+    decoded: Vec<Option<Instruction>>,
+    // and already translated into blocks, for the blocks backend
+    blocks: Blocks,
+    pub screen: Framebuffer,
+    pub stack: Vec<u16>,
+    // keys held down, and keys that went down since the last frame
+    pub keys: [bool; 16],
+    pub pressed: [bool; 16],
+    // bit n is set once the game has checked key n
+    pub polled_keys: u16,
+    // VIP quirk: a sprite draw waits for the next vertical blank
+    pub display_wait: bool,
+    pub waiting_for_vblank: bool,
+    pub waiting_for_key: bool,
+    // execution stops once pc runs past the end of the program
+    pub rom_end: u16,
+    pub frame: u64,
+    // instructions run since the start, what --benchmark measures
+    pub instructions: u64,
+    pub timing: Timing,
+    pub backend: Backend,
+    // VIP timing: cycles left over from, or owed to, the last frame
+    cycles: i64,
+    pub tracer: Option<Tracer>,
+    pub profiler: Option<Profiler>,
+    pub coverage: Option<Coverage>,
+    pub watcher: Option<Watcher>,
+    // set when the watcher breaks, frontends stop running frames until it is cleared
+    pub stopped: bool,
+    // keys are recorded to or played back from the movie
+    pub movie: Option<Movie>,
+    // CXNN draws from this, seeded for a game that plays out the same every time
+    rng: StdRng,
+}
+
+impl Chip8State {
+    pub fn new(memory: [u8; 1024 * 4], screen: Framebuffer, 
+        v: [u8;16], delay: u8) -> Self {Chip8State {
+            memory,
+            decoded: vec![None; 1024 * 4],
+            blocks: Blocks::default(),
+            screen,
+            sp: 0,
+            pc: 0x200,
+            v,
+            font: [0; 0x50],
+            i: 0x0,
+            delay,
+            stack: Vec::with_capacity(16),
+            keys: [false; 16],
+            pressed: [false; 16],
+            polled_keys: 0,
+            display_wait: false,
+            waiting_for_vblank: false,
+            waiting_for_key: false,
+            rom_end: 0x200,
+            frame: 0,
+            instructions: 0,
+            timing: Timing::default(),
+            backend: Backend::default(),
+            cycles: 0,
+            tracer: None,
+            profiler: None,
+            coverage: None,
+            watcher: None,
+            stopped: false,
+            movie: None,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn builder() -> Chip8Builder {
+        Chip8Builder::default()
+    }
+
+    /// Puts the font at 0x0 and the program at 0x200, where chip-8 expects them.
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Fault> {
+        if rom.len() > self.memory.len() - 0x200 {
+            return Err(Fault::RomTooLarge {size: rom.len()});
+        }
+
+        self.font = FONT;
+        self.write_memory(0x0, &FONT);
+        self.write_memory(0x200, rom);
+        self.rom_end = 0x200 + rom.len() as u16;
+
+        Ok(())
+    }
+
+    /// Runs a single instruction as if it were at `pc`.
+    pub fn execute(&mut self, opcode: u16) -> Result<(), Fault> {
+        let pc = self.memory_range(self.pc as usize, 2)?;
+        self.write_memory(pc.start, &opcode.to_be_bytes());
+        self.step()
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Writes `bytes` from `address`, past the end of memory panics.
+    pub fn write_memory(&mut self, address: usize, bytes: &[u8]) {
+        let end = address + bytes.len();
+        self.memory[address..end].copy_from_slice(bytes);
+        // an instruction starting on the byte before is changed too
+        self.decoded[address.saturating_sub(1)..end].fill(None);
+        self.blocks.forget(address..end);
+    }
+
+    /// The instruction at `pc`, decoded the first time it runs.
+    // inlined, like run, as this is most of what step does
+    #[inline(always)]
+    fn decoded(&mut self) -> Result<Instruction, Fault> {
+        if let Some(Some(instruction)) = self.decoded.get(self.pc as usize) {
+            return Ok(*instruction);
+        }
+
+        let instruction = Instruction::decode(self.fetch()?);
+        self.decoded[self.pc as usize] = Some(instruction);
+        Ok(instruction)
+    }
+
+    /// The opcode at `pc`.
+    pub fn fetch(&self) -> Result<u16, Fault> {
+        let pc = self.memory_range(self.pc as usize, 2)?;
+        Ok(u16::from_be_bytes([self.memory[pc.start], self.memory[pc.start + 1]]))
+    }
+
+    /// Checks that `length` bytes from `start` are all in memory.
+    fn memory_range(&self, start: usize, length: usize) -> Result<Range<usize>, Fault> {
+        if start + length > self.memory.len() {
+            return Err(Fault::MemoryOutOfRange {pc: self.pc, address: (start + length - 1).max(start)});
+        }
+
+        Ok(start..start + length)
+    }
+
+    pub fn halted(&self) -> bool {
+        self.pc >= self.rom_end
+    }
+
+    /// Starts the random numbers CXNN gives over from `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Updates the keys held down, remembering which ones were just pressed.
+    /// A movie that is playing back overrides them.
+    pub fn set_keys(&mut self, keys: [bool; 16]) {
+        let keys = match &mut self.movie {
+            Some(movie) => movie.input(self.frame, keys),
+            None => keys,
+        };
+        for (key, down) in keys.iter().enumerate() {
+            self.pressed[key] |= *down && !self.keys[key];
+        }
+        self.keys = keys;
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            v: self.v,
+            i: self.i,
+            sp: self.sp,
+            delay: self.delay,
+        }
+    }
+
+    /// Tells the watcher and the coverage how the program used memory.
+    fn accessed(&mut self, access: Access, addresses: Range<usize>) {
+        if let Some(watcher) = &mut self.watcher {
+            if watcher.mark(access, addresses.clone(), self.pc, self.frame) && watcher.break_on_event {
+                self.stopped = true;
+                // the instruction runs, and is counted, once the game carries on
+                return;
+            }
+        }
+
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark(access, addresses);
+        }
+    }
+
+    pub fn step(&mut self) -> Result<(), Fault> {
+        // nothing is watching, so straight to the instruction, with the rest kept out of the way
+        if !self.watched() {
+            let instruction = self.decoded()?;
+            self.run(instruction)?;
+            self.pc = self.pc.wrapping_add(2);
+            self.instructions += 1;
+            return Ok(());
+        }
+
+        self.step_watched()
+    }
+
+    // step with the tracer, profiler, coverage or watcher looking on
+    #[cold]
+    fn step_watched(&mut self) -> Result<(), Fault> {
+        let pc = self.pc;
+        let opcode = self.fetch()?;
+        let instruction = self.decoded()?;
+        self.accessed(Access::Fetch, pc as usize..pc as usize + 1);
+        if self.stopped {
+            return Ok(());
+        }
+        if let Some(profiler) = &mut self.profiler {
+            let cost = match self.timing {
+                Timing::Ipf(_) => 1,
+                Timing::Vip => timing::vip_cycles(opcode, &self.v),
+            };
+            profiler.record(pc, opcode, cost, &self.stack, &self.memory);
+        }
+
+        let traced = self.tracer.as_ref().is_some_and(|tracer| tracer.filter.matches(self.frame, pc, opcode));
+
+        if !traced {
+            self.run(instruction)?;
+            self.pc = self.pc.wrapping_add(2);
+            self.instructions += 1;
+            return Ok(());
+        }
+
+        let before = self.registers();
+        self.run(instruction)?;
+        self.pc = self.pc.wrapping_add(2);
+        self.instructions += 1;
+
+        let mnemonic = instruction.to_string();
+        let step = Step {
+            frame: self.frame,
+            pc,
+            opcode,
+            mnemonic: &mnemonic,
+            next_pc: self.pc,
+            before,
+            after: self.registers(),
+        };
+        if let Some(tracer) = &mut self.tracer {
+            tracer.record(&step);
+        }
+
+        Ok(())
+    }
+
+    // the tracer, profiler, coverage and watcher need to see every instruction
+    fn watched(&self) -> bool {
+        self.tracer.is_some() || self.profiler.is_some() || self.coverage.is_some() || self.watcher.is_some()
+    }
+
+    fn paused(&self) -> bool {
+        self.halted() || self.stopped || self.waiting_for_vblank || self.waiting_for_key
+    }
+
+    /// Runs one 60 Hz frame's worth of instructions, then the timers tick.
+    /// A fault stops the frame straight away.
+    pub fn run_frame(&mut self) -> Result<(), Fault> {
+        // blocks skip over what the instruments want to see, so those always get the interpreter
+        if self.backend == Backend::Blocks && !self.watched() {
+            self.run_blocks()?;
+        } else {
+            self.interpret()?;
+        }
+
+        if self.profiler.is_some() {
+            // still running when the frame ended, so the next instruction did not fit
+            let next = self.fetch().map_or(0, |opcode| match self.timing {
+                Timing::Ipf(_) => 1,
+                Timing::Vip => timing::vip_cycles(opcode, &self.v),
+            });
+            let cut_off = if self.paused() {None} else {Some(next)};
+            if let Some(profiler) = &mut self.profiler {
+                profiler.end_frame(cut_off);
+            }
+        }
+
+        self.waiting_for_vblank = false;
+        self.waiting_for_key = false;
+        self.pressed = [false; 16];
+        if self.delay > 0 {
+            self.delay -= 1;
+        }
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    // a frame's worth, one instruction at a time
+    fn interpret(&mut self) -> Result<(), Fault> {
+        match self.timing {
+            Timing::Ipf(ipf) => {
+                for _ in 0..ipf {
+                    if self.paused() {
+                        break;
+                    }
+
+                    self.step()?;
+                }
+            },
+            Timing::Vip => {
+                // an instruction that runs past the end of the frame eats into the next one
+                self.cycles += timing::VIP_BUDGET as i64;
+                while self.cycles > 0 {
+                    if self.paused() {
+                        // waiting wastes the rest of the frame
+                        self.cycles = 0;
+                        break;
+                    }
+
+                    self.cycles -= timing::vip_cycles(self.fetch()?, &self.v) as i64;
+                    self.step()?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Does what `interpret` does, a block at a time: the frame's budget and
+    /// pauses are only checked between instructions that can change them.
+    fn run_blocks(&mut self) -> Result<(), Fault> {
+        match self.timing {
+            Timing::Ipf(ipf) => {
+                let mut left = ipf as usize;
+                while left > 0 && !self.paused() {
+                    let Some(ops) = self.blocks.block(&self.memory, self.pc, self.rom_end) else {
+                        // nothing to compile, which is the interpreter's fault to report
+                        left -= 1;
+                        self.step()?;
+                        continue;
+                    };
+
+                    // the budget can run out part way through
+                    let length = ops.len().min(left);
+                    left -= length;
+                    for index in ops.start..ops.start + length {
+                        let op = self.blocks.op(index);
+                        (op.run)(self, op.opcode)?;
+                        self.pc = self.pc.wrapping_add(2);
+                        self.instructions += 1;
+                    }
+                }
+            },
+            Timing::Vip => {
+                self.cycles += timing::VIP_BUDGET as i64;
+                while self.cycles > 0 {
+                    if self.paused() {
+                        self.cycles = 0;
+                        break;
+                    }
+                    let Some(ops) = self.blocks.block(&self.memory, self.pc, self.rom_end) else {
+                        self.cycles -= timing::vip_cycles(self.fetch()?, &self.v) as i64;
+                        self.step()?;
+                        continue;
+                    };
+
+                    for index in ops {
+                        if self.cycles <= 0 {
+                            break;
+                        }
+                        let op = self.blocks.op(index);
+                        self.cycles -= timing::vip_cycles(op.opcode, &self.v) as i64;
+                        (op.run)(self, op.opcode)?;
+                        self.pc = self.pc.wrapping_add(2);
+                        self.instructions += 1;
+                    }
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Sets up a machine in a particular state, which is handy for tests:
+/// `Chip8State::builder().v(0, 0xff).v(1, 1).build()` then `execute(0x8014)`.
+/// It starts out with the font loaded and `pc` at 0x200.
+pub struct Chip8Builder {
+    chip8: Chip8State,
+}
+
+impl Default for Chip8Builder {
+    fn default() -> Self {
+        let mut chip8 = Chip8State::new([0; 4096], Framebuffer::new(), [0; 16], 0);
+        chip8.load_rom(&[]).expect("an empty program fits");
+
+        Chip8Builder {chip8}
+    }
+}
+
+impl Chip8Builder {
+    pub fn v(mut self, register: usize, value: u8) -> Self {
+        self.chip8.v[register] = value;
+        self
+    }
+
+    pub fn i(mut self, i: u16) -> Self {
+        self.chip8.i = i;
+        self
+    }
+
+    pub fn pc(mut self, pc: u16) -> Self {
+        self.chip8.pc = pc;
+        self
+    }
+
+    pub fn delay(mut self, delay: u8) -> Self {
+        self.chip8.delay = delay;
+        self
+    }
+
+    /// Pretends these calls were made, innermost last.
+    pub fn stack(mut self, calls: &[u16]) -> Self {
+        self.chip8.stack = calls.to_vec();
+        self.chip8.sp = calls.len() as u16;
+        self
+    }
+
+    pub fn memory(mut self, address: u16, bytes: &[u8]) -> Self {
+        self.chip8.write_memory(address as usize, bytes);
+        self
+    }
+
+    pub fn rom(mut self, rom: &[u8]) -> Self {
+        self.chip8.load_rom(rom).expect("the program should fit in memory");
+        self
+    }
+
+    pub fn key(mut self, key: usize) -> Self {
+        self.chip8.keys[key] = true;
+        self.chip8.pressed[key] = true;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.chip8.seed(seed);
+        self
+    }
+
+    pub fn hires(mut self) -> Self {
+        self.chip8.screen.set_hires(true);
+        self
+    }
+
+    pub fn build(self) -> Chip8State {
+        self.chip8
+    }
+}
+
+impl Chip8State {
+    /// Carries out a decoded instruction. `pc` still points at it, and
+    /// instructions that jump set it to 2 before where they go.
+    #[inline(always)]
+    pub(crate) fn run(&mut self, instruction: Instruction) -> Result<(), Fault> {
+        match instruction {
+            Instruction::Clear => self.screen.clear(),
+            Instruction::Return => {
+                self.pc = self.stack.pop().ok_or(Fault::StackUnderflow {pc: self.pc})?;
+                self.sp -= 1;
+            },
+            Instruction::Lores => self.screen.set_hires(false),
+            Instruction::Hires => self.screen.set_hires(true),
+            Instruction::Jump(nnn) => self.pc = nnn.wrapping_sub(2),
+            Instruction::Call(nnn) => {
+                if self.stack.len() >= STACK_SIZE {
+                    return Err(Fault::StackOverflow {pc: self.pc});
+                }
+                self.sp += 1;
+                self.stack.push(self.pc);
+                self.pc = nnn.wrapping_sub(2);
+            },
+            Instruction::SkipEqual {x, nn} => if self.v[x as usize] == nn {self.pc += 2},
+            Instruction::SkipNotEqual {x, nn} => if self.v[x as usize] != nn {self.pc += 2},
+            Instruction::SkipEqualRegister {x, y} => if self.v[x as usize] == self.v[y as usize] {self.pc += 2},
+            Instruction::SkipNotEqualRegister {x, y} => if self.v[x as usize] != self.v[y as usize] {self.pc += 2},
+            Instruction::Set {x, nn} => self.v[x as usize] = nn,
+            Instruction::Add {x, nn} => self.v[x as usize] = self.v[x as usize].wrapping_add(nn),
+            Instruction::Move {x, y} => self.v[x as usize] = self.v[y as usize],
+            // the VIP's logic instructions leave VF cleared
+            Instruction::Or {x, y} => self.arithmetic(x, y, |v_x, v_y| (v_x | v_y, 0)),
+            Instruction::And {x, y} => self.arithmetic(x, y, |v_x, v_y| (v_x & v_y, 0)),
+            Instruction::Xor {x, y} => self.arithmetic(x, y, |v_x, v_y| (v_x ^ v_y, 0)),
+            Instruction::AddRegister {x, y} => self.arithmetic(x, y, |v_x, v_y| {
+                let (sum, carry) = v_x.overflowing_add(v_y);
+                (sum, carry as u8)
+            }),
+            // no borrow when they are equal either
+            Instruction::Subtract {x, y} => self.arithmetic(x, y, |v_x, v_y| (v_x.wrapping_sub(v_y), (v_x >= v_y) as u8)),
+            // the shifts work on VY, as on the VIP
+            Instruction::ShiftRight {x, y} => self.arithmetic(x, y, |_, v_y| (v_y >> 1, v_y & 1)),
+            Instruction::SubtractFrom {x, y} => self.arithmetic(x, y, |v_x, v_y| (v_y.wrapping_sub(v_x), (v_y >= v_x) as u8)),
+            Instruction::ShiftLeft {x, y} => self.arithmetic(x, y, |_, v_y| (v_y << 1, v_y >> 7)),
+            Instruction::SetI(nnn) => self.i = nnn,
+            Instruction::JumpV0(nnn) => self.pc = (nnn + self.v[0] as u16).wrapping_sub(2),
+            Instruction::Random {x, nn} => {
+                let random_byte: u8 = self.rng.gen_range(0..=255);
+                self.v[x as usize] = random_byte & nn;
+            },
+            Instruction::Draw {x, y, n} => self.draw(x, y, n)?,
+            Instruction::SkipKey {x} => if self.key(x)? {self.pc += 2},
+            Instruction::SkipNotKey {x} => if !self.key(x)? {self.pc += 2},
+            Instruction::GetDelay {x} => self.v[x as usize] = self.delay,
+            Instruction::WaitKey {x} => {
+                self.polled_keys = 0xffff;
+
+                // wait for a key to go down, the frame loop comes back here next frame
+                match self.pressed.iter().position(|&pressed| pressed) {
+                    Some(key) => {
+                        self.v[x as usize] = key as u8;
+                        self.pressed[key] = false;
+                    },
+                    None => {
+                        self.waiting_for_key = true;
+                        self.pc = self.pc.wrapping_sub(2);
+                    },
+                }
+            },
+            Instruction::SetDelay {x} => self.delay = self.v[x as usize],
+            Instruction::SetSound {..} => {},
+            Instruction::AddI {x} => self.i = self.i.wrapping_add(self.v[x as usize] as u16),
+            // only the low digit counts, like on the VIP
+            Instruction::Font {x} => self.i = (self.v[x as usize] & 0xf) as u16 * 5,
+            Instruction::Decimal {x} => {
+                let v_x = self.v[x as usize];
+                let bcd = self.memory_range(self.i as usize, 3)?;
+                self.write_memory(bcd.start, &[v_x / 100, v_x / 10 % 10, v_x % 10]);
+                self.accessed(Access::Write, bcd);
+            },
+            Instruction::Store {x} => {
+                let registers = self.memory_range(self.i as usize, x as usize + 1)?;
+                let v = self.v;
+                self.write_memory(registers.start, &v[..=x as usize]);
+                self.accessed(Access::Write, registers);
+                self.i = self.i.wrapping_add(x as u16 + 1);
+            },
+            Instruction::Load {x} => {
+                let registers = self.memory_range(self.i as usize, x as usize + 1)?;
+                self.v[..=x as usize].copy_from_slice(&self.memory[registers.clone()]);
+                self.accessed(Access::Load, registers);
+                self.i = self.i.wrapping_add(x as u16 + 1);
+            },
+            Instruction::Unknown(_) => {},
+        }
+
+        Ok(())
+    }
+
+    /// 8XY1 to 8XYE: the result goes in VX and then the flag in VF, so the
+    /// flag wins when X is F.
+    fn arithmetic(&mut self, x: u8, y: u8, operation: impl Fn(u8, u8) -> (u8, u8)) {
+        let (result, flag) = operation(self.v[x as usize], self.v[y as usize]);
+        self.v[x as usize] = result;
+        self.v[0xf] = flag;
+    }
+
+    /// Whether the key in VX is down, for EX9E and EXA1.
+    fn key(&mut self, x: u8) -> Result<bool, Fault> {
+        let key = self.v[x as usize];
+        if key > 0xf {
+            return Err(Fault::InvalidKey {pc: self.pc, key});
+        }
+
+        self.polled_keys |= 1 << key;
+        Ok(self.keys[key as usize])
+    }
+
+    /// DXYN: XORs N rows of the sprite at I onto the screen, clipped at the
+    /// edges, and sets VF if any pixel was turned off.
+    fn draw(&mut self, x: u8, y: u8, n: u8) -> Result<(), Fault> {
+        let width = self.screen.width();
+        let height = self.screen.height();
+        // VX is read once, it may be VF and get cleared below
+        let left = self.v[x as usize] as usize % width;
+        let top = self.v[y as usize] as usize % height;
+        let sprite = self.memory_range(self.i as usize, n as usize)?;
+        self.v[0xf] = 0;
+        self.accessed(Access::Sprite, sprite.clone());
+
+        for (row, address) in sprite.enumerate() {
+            let y = top + row;
+            if y >= height {
+                break;
+            }
+
+            let byte = self.memory[address];
+            for column in 0..8.min(width - left) {
+                if byte >> (7 - column) & 1 == 1 && self.screen.flip(0, left + column, y) {
+                    self.v[0xf] = 1;
+                }
+            }
+        }
+
+        self.waiting_for_vblank = self.display_wait;
+        Ok(())
+    }
+}
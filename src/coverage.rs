@@ -0,0 +1,107 @@
+use crate::instruction::Instruction;
+use std::io::{self, Write};
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    // fetched as an instruction
+    Fetch,
+    // read as sprite data by DXYN
+    Sprite,
+    // read into registers by FX65
+    Load,
+    // written by FX33 or FX55
+    Write,
+}
+
+/// Remembers how every byte of memory was used.
+pub struct Coverage {
+    fetched: Vec<u64>,
+    read: Vec<bool>,
+    written: Vec<bool>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Coverage {
+            fetched: vec![0; 4096],
+            read: vec![false; 4096],
+            written: vec![false; 4096],
+        }
+    }
+}
+
+impl Coverage {
+    pub fn mark(&mut self, access: Access, addresses: Range<usize>) {
+        for address in addresses {
+            let address = address & 0xfff;
+            match access {
+                Access::Fetch => self.fetched[address] += 1,
+                Access::Sprite | Access::Load => self.read[address] = true,
+                Access::Write => self.written[address] = true,
+            }
+        }
+    }
+
+    /// Lists the program between `start` and `end` like gcov: how often each
+    /// instruction ran and what it was, `#####` for bytes that were never used, and flags for
+    /// bytes read (`r`) or written (`w`) as data. Sprite data is drawn out.
+    pub fn report(&self, out: &mut impl Write, memory: &[u8], start: usize, end: usize) -> io::Result<()> {
+        let size = end.saturating_sub(start).max(1);
+        let executed = (start..end).filter(|&address| self.executed(address)).count();
+        let read = (start..end).filter(|&address| self.read[address]).count();
+        let written = (start..end).filter(|&address| self.written[address]).count();
+
+        writeln!(out, "{} of {} bytes executed ({:.1}%), {} read as data, {} written",
+            executed, size, executed as f64 * 100.0 / size as f64, read, written)?;
+        writeln!(out, "memory is shown as it was at the end, after any writes\n")?;
+
+        let mut at = start;
+        while at < end {
+            // code that was jumped into at an odd address shifts the listing by a byte
+            if !self.executed(at) && self.fetched[(at + 1) & 0xfff] > 0 {
+                self.line(out, memory, at, 1)?;
+                at += 1;
+            } else {
+                let length = if at + 1 < end {2} else {1};
+                self.line(out, memory, at, length)?;
+                at += length;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn executed(&self, address: usize) -> bool {
+        self.fetched[address] > 0 || (address > 0 && self.fetched[address - 1] > 0)
+    }
+
+    fn line(&self, out: &mut impl Write, memory: &[u8], at: usize, length: usize) -> io::Result<()> {
+        let bytes = &memory[at..at + length];
+        let range = at..at + length;
+        let read = range.clone().any(|address| self.read[address]);
+        let written = range.clone().any(|address| self.written[address]);
+
+        let count = match self.fetched[at] {
+            0 if read || written || range.clone().any(|address| self.executed(address)) => "-".to_string(),
+            0 => "#####".to_string(),
+            count => count.to_string(),
+        };
+        let flags = format!("{}{}", if read {"r"} else {" "}, if written {"w"} else {" "});
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        let annotation = if self.fetched[at] > 0 && length == 2 {
+            Instruction::decode(u16::from_be_bytes([bytes[0], bytes[1]])).to_string()
+        } else if read {
+            bytes.iter()
+                .map(|byte| (0..8).rev().map(|bit| if byte >> bit & 1 == 1 {'█'} else {'.'}).collect::<String>())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            String::new()
+        };
+
+        let line = format!("{:>10} {} {:03x}: {:<4}  {}", count, flags, at, hex, annotation);
+        writeln!(out, "{}", line.trim_end())
+    }
+}
@@ -0,0 +1,140 @@
+use crate::framebuffer::{self, Framebuffer};
+use crate::keypad::KeypadOverlay;
+use crate::palette::Palette;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowContext};
+
+/// How frames are shown to cut down on the flicker of games that erase and
+/// redraw their sprites every frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flicker {
+    // show the screen as it is at the end of each frame
+    Off,
+    // average the last two frames
+    Blend,
+    // let pixels that were switched off fade out
+    Phosphor,
+}
+
+impl Flicker {
+    pub fn named(name: &str) -> Option<Flicker> {
+        match name {
+            "off" => Some(Flicker::Off),
+            "blend" => Some(Flicker::Blend),
+            "phosphor" => Some(Flicker::Phosphor),
+            _ => None,
+        }
+    }
+}
+
+pub struct Display<'a> {
+    canvas: Canvas<Window>,
+    texture: Texture<'a>,
+    pub keypad: KeypadOverlay,
+    palette: Palette,
+    flicker: Flicker,
+    fade: f32,
+    // what was on screen last time, for blending and fading
+    shown: Vec<f32>,
+}
+
+impl<'a> Display<'a> {
+    pub fn new(mut canvas: Canvas<Window>, creator: &'a TextureCreator<WindowContext>, keypad: KeypadOverlay,
+        palette: Palette, flicker: Flicker, fade: f32) -> Self {
+        let (width, height) = (framebuffer::WIDTH as u32, framebuffer::HEIGHT as u32);
+
+        // scale by whole numbers only and letterbox whatever space is left over
+        canvas.set_integer_scale(true).unwrap();
+        canvas.set_logical_size(width + keypad.width(height), height).unwrap();
+
+        let (r, g, b) = palette.rgb(0);
+        canvas.set_draw_color(Color::RGB(r, g, b));
+        canvas.clear();
+
+        let texture = creator
+            .create_texture_target(PixelFormatEnum::RGB24, framebuffer::HIRES_WIDTH as u32, framebuffer::HIRES_HEIGHT as u32)
+            .unwrap();
+
+        Display {
+            canvas,
+            texture,
+            keypad,
+            palette,
+            flicker,
+            fade,
+            shown: Vec::new(),
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.canvas.window_mut().set_title(title).unwrap();
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        let window = self.canvas.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+
+        window.set_fullscreen(fullscreen).unwrap();
+    }
+
+    /// Shows the screen, the frame loop calls this once per 60 Hz frame.
+    pub fn present(&mut self, screen: &Framebuffer, font: &[u8], pressed: [bool; 16]) {
+        let (width, height) = (screen.width() as u32, screen.height() as u32);
+        let mut pixels = vec![0; (width * height * 3) as usize];
+        self.palette.apply(screen.pixels(), &mut pixels);
+        self.filter(screen, &mut pixels);
+
+        // follow the game into and out of high resolution mode
+        let logical_size = (width + self.keypad.width(height), height);
+        if self.canvas.logical_size() != logical_size {
+            self.canvas.set_logical_size(logical_size.0, logical_size.1).unwrap();
+        }
+        self.keypad.layout(width, height);
+
+        let area = Rect::new(0, 0, width, height);
+        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.clear();
+        self.texture.update(area, &pixels, (width * 3) as usize).unwrap();
+        self.canvas.copy(&self.texture, area, area).unwrap();
+
+        if self.keypad.enabled {
+            self.keypad.draw(&mut self.canvas, font, pressed);
+        }
+
+        self.canvas.present();
+    }
+
+    fn filter(&mut self, screen: &Framebuffer, pixels: &mut [u8]) {
+        if self.shown.len() != pixels.len() {
+            self.shown = pixels.iter().map(|&channel| channel as f32).collect();
+        }
+
+        match self.flicker {
+            Flicker::Off => {},
+            Flicker::Blend => {
+                for (channel, shown) in pixels.iter_mut().zip(self.shown.iter_mut()) {
+                    let current = *channel as f32;
+                    *channel = ((current + *shown) / 2.0) as u8;
+                    *shown = current;
+                }
+            },
+            Flicker::Phosphor => {
+                // lit pixels show up at once, dark ones fade towards the background
+                for ((rgb, shown), lit) in pixels.chunks_exact_mut(3)
+                    .zip(self.shown.chunks_exact_mut(3))
+                    .zip(screen.pixels().map(|pixel| pixel != 0)) {
+                    for (channel, shown) in rgb.iter_mut().zip(shown.iter_mut()) {
+                        let target = *channel as f32;
+                        *shown = if lit {target} else {*shown + (target - *shown) * self.fade};
+                        *channel = shown.round() as u8;
+                    }
+                }
+            },
+        }
+    }
+}
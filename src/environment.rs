@@ -0,0 +1,129 @@
+use crate::blocks::Backend;
+use crate::chip8::{Chip8State, Fault};
+use crate::framebuffer::Framebuffer;
+use crate::timing::Timing;
+
+/// A number read out of the machine, such as a score or the lives left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Extractor {
+    // the byte at an address
+    Byte(u16),
+    // decimal digits one to a byte from an address, most significant first, the way FX33 stores them
+    Bcd {address: u16, digits: u8},
+    // a V register, for games that keep the score in one
+    Register(u8),
+}
+
+impl Extractor {
+    /// Reads the number, with anything past the end of memory as 0.
+    pub fn read(&self, chip8: &Chip8State) -> u64 {
+        let byte = |address: usize| chip8.memory().get(address).copied().unwrap_or(0) as u64;
+        match *self {
+            Extractor::Byte(address) => byte(address as usize),
+            Extractor::Bcd {address, digits} => {
+                (0..digits as usize).fold(0, |number, digit| number * 10 + byte(address as usize + digit))
+            },
+            Extractor::Register(x) => chip8.v[x as usize & 0xf] as u64,
+        }
+    }
+}
+
+/// A game for an agent to play, gym style: `reset` starts an episode and
+/// `step` holds the keys down for `frameskip` frames and says what came of
+/// it. The reward is how much the `reward` number went up, and the episode
+/// is done once any of the `done` numbers reads its value, after
+/// `max_frames`, or when the program halts or faults.
+///
+/// Settings changed after `new` take effect from the next `reset`.
+pub struct Environment {
+    rom: Vec<u8>,
+    pub timing: Timing,
+    pub backend: Backend,
+    pub display_wait: bool,
+    pub frameskip: u32,
+    pub reward: Option<Extractor>,
+    pub done: Vec<(Extractor, u64)>,
+    pub max_frames: Option<u64>,
+    chip8: Chip8State,
+    // the reward number when it was last read
+    score: u64,
+    fault: Option<Fault>,
+}
+
+impl Environment {
+    /// An environment for `rom` with one frame a step at 10 instructions a
+    /// frame, no reward and no end but the program's own, reset with seed 0.
+    pub fn new(rom: &[u8]) -> Result<Self, Fault> {
+        let mut environment = Environment {
+            rom: rom.to_vec(),
+            timing: Timing::Ipf(10),
+            backend: Backend::default(),
+            display_wait: false,
+            frameskip: 1,
+            reward: None,
+            done: Vec::new(),
+            max_frames: None,
+            chip8: Chip8State::builder().build(),
+            score: 0,
+            fault: None,
+        };
+        environment.chip8.load_rom(rom)?;
+        environment.reset(0);
+
+        Ok(environment)
+    }
+
+    /// Starts a new episode from power on, with CXNN drawing from `seed`,
+    /// and returns the first observation.
+    pub fn reset(&mut self, seed: u64) -> &Framebuffer {
+        let mut chip8 = Chip8State::builder().rom(&self.rom).seed(seed).build();
+        chip8.timing = self.timing;
+        chip8.backend = self.backend;
+        chip8.display_wait = self.display_wait;
+
+        self.chip8 = chip8;
+        self.score = self.reward.map_or(0, |reward| reward.read(&self.chip8));
+        self.fault = None;
+        &self.chip8.screen
+    }
+
+    /// Holds `keys` down for `frameskip` frames, or until the episode is
+    /// done, and returns the screen, the reward over those frames and
+    /// whether the episode is done. Once it is done, stepping changes nothing.
+    pub fn step(&mut self, keys: [bool; 16]) -> (&Framebuffer, f64, bool) {
+        let mut reward = 0.0;
+
+        for _ in 0..self.frameskip.max(1) {
+            if self.done() {
+                break;
+            }
+
+            self.chip8.set_keys(keys);
+            self.fault = self.chip8.run_frame().err();
+            if let Some(extractor) = self.reward {
+                let score = extractor.read(&self.chip8);
+                reward += score as f64 - self.score as f64;
+                self.score = score;
+            }
+        }
+
+        (&self.chip8.screen, reward, self.done())
+    }
+
+    pub fn done(&self) -> bool {
+        self.fault.is_some()
+            || self.chip8.halted()
+            || self.max_frames.is_some_and(|frames| self.chip8.frame >= frames)
+            || self.done.iter().any(|(extractor, value)| extractor.read(&self.chip8) == *value)
+    }
+
+    /// What ended the episode, when it was the program faulting.
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+
+    /// The machine itself, to look at memory or the registers.
+    pub fn chip8(&self) -> &Chip8State {
+        &self.chip8
+    }
+}
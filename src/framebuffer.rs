@@ -0,0 +1,96 @@
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 32;
+// SCHIP's high resolution mode doubles both sides of the screen
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+// XO-CHIP draws on two bit planes, plain chip-8 only uses the first one
+pub const PLANES: usize = 2;
+
+/// The logical screen, one bit per pixel for each plane. Row `y` of a plane
+/// is a `u128` whose most significant bit is the leftmost pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    planes: [[u128; HIRES_HEIGHT]; PLANES],
+    hires: bool,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        Framebuffer {
+            planes: [[0; HIRES_HEIGHT]; PLANES],
+            hires: false,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires {HIRES_WIDTH} else {WIDTH}
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {HIRES_HEIGHT} else {HEIGHT}
+    }
+
+    /// Switches between the 64x32 and 128x64 modes, clearing the screen.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.planes = [[0; HIRES_HEIGHT]; PLANES];
+    }
+
+    /// Toggles a pixel on `plane` and returns true if it was lit before,
+    /// which is what sprite collision is based on.
+    pub fn flip(&mut self, plane: usize, x: usize, y: usize) -> bool {
+        let mask = 1 << (HIRES_WIDTH - 1 - x);
+        let row = &mut self.planes[plane][y];
+        let was_lit = *row & mask != 0;
+        *row ^= mask;
+
+        was_lit
+    }
+
+    /// Returns the planes the pixel is lit on, bit 0 being the first plane.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let shift = HIRES_WIDTH - 1 - x;
+
+        self.planes.iter()
+            .enumerate()
+            .fold(0, |value, (plane, rows)| value | (((rows[y] >> shift) & 1) as u8) << plane)
+    }
+
+    /// Pixel values row by row, stretched or shrunk to `width` x `height`.
+    pub fn resized(&self, width: usize, height: usize) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(self.pixel(x * self.width() / width, y * self.height() / height));
+            }
+        }
+
+        pixels
+    }
+
+    /// FNV-1a hash of the resolution and every pixel, to tell screens apart
+    /// without keeping them around.
+    pub fn checksum(&self) -> u64 {
+        let size = [self.width() as u8, self.height() as u8];
+
+        size.into_iter().chain(self.pixels()).fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// Pixel values row by row, starting at the top left corner.
+    pub fn pixels(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.height()).flat_map(move |y| (0..self.width()).map(move |x| self.pixel(x, y)))
+    }
+}
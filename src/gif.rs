@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// 4 colours, so indices need 2 bits
+const MIN_CODE_SIZE: u8 = 2;
+const MAX_CODES: u16 = 4096;
+// most viewers slow down frames shorter than 2/100 s
+const MIN_DELAY: u64 = 2;
+
+/// Writes an animated GIF one 60 Hz frame at a time. Frames that do not
+/// change are merged, and frames too short for a GIF to show are dropped.
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    frame: u64,
+    // the frame waiting to be written and the frame number it appeared on
+    pending: Option<(Vec<u8>, u64)>,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(mut out: W, width: u16, height: u16, colors: &[(u8, u8, u8); 4]) -> io::Result<Self> {
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // global colour table of 2^(1 + 1) colours, no background, square pixels
+        out.write_all(&[0x80 | (1 << 4) | 1, 0, 0])?;
+        for &(r, g, b) in colors {
+            out.write_all(&[r, g, b])?;
+        }
+        // loop forever
+        out.write_all(&[0x21, 0xff, 0x0b])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(GifWriter {
+            out,
+            width,
+            height,
+            frame: 0,
+            pending: None,
+        })
+    }
+
+    /// Adds the screen for the next 60 Hz frame, one palette index per pixel.
+    pub fn frame(&mut self, indices: Vec<u8>) -> io::Result<()> {
+        let now = self.frame;
+        self.frame += 1;
+
+        match self.pending.take() {
+            Some((pending, start)) if pending == indices => self.pending = Some((pending, start)),
+            Some((pending, start)) if centiseconds(now) - centiseconds(start) >= MIN_DELAY => {
+                self.write_image(&pending, centiseconds(now) - centiseconds(start))?;
+                self.pending = Some((indices, now));
+            },
+            // the pending frame was too short to show, this one replaces it
+            Some((_, start)) => self.pending = Some((indices, start)),
+            None => self.pending = Some((indices, now)),
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some((pending, start)) = self.pending.take() {
+            let delay = centiseconds(self.frame) - centiseconds(start);
+            self.write_image(&pending, delay.max(MIN_DELAY))?;
+        }
+
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+
+    fn write_image(&mut self, indices: &[u8], delay: u64) -> io::Result<()> {
+        let delay = delay.min(u16::MAX as u64) as u16;
+
+        // graphic control extension with the frame delay
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+
+        // image descriptor covering the whole screen
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x00, MIN_CODE_SIZE])?;
+
+        for block in lzw(indices).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x00])
+    }
+}
+
+fn centiseconds(frame: u64) -> u64 {
+    frame * 100 / 60
+}
+
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut bits = Bits::default();
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = MIN_CODE_SIZE + 1;
+
+    bits.write(clear, size);
+    let Some((&first, rest)) = indices.split_first() else {
+        bits.write(end, size);
+        return bits.finish();
+    };
+
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = codes.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        bits.write(prefix, size);
+        codes.insert((prefix, index), next);
+        if next >= 1 << size {
+            size += 1;
+        }
+        next += 1;
+
+        // start over once the table is full
+        if next == MAX_CODES {
+            bits.write(clear, size);
+            codes.clear();
+            next = end + 1;
+            size = MIN_CODE_SIZE + 1;
+        }
+
+        prefix = index as u16;
+    }
+
+    bits.write(prefix, size);
+    // the decoder adds one more code after reading the last one
+    if next == 1 << size && size < 12 {
+        size += 1;
+    }
+    bits.write(end, size);
+
+    bits.finish()
+}
+
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    current: u32,
+    used: u8,
+}
+
+impl Bits {
+    fn write(&mut self, code: u16, size: u8) {
+        self.current |= (code as u32) << self.used;
+        self.used += size;
+
+        while self.used >= 8 {
+            self.bytes.push(self.current as u8);
+            self.current >>= 8;
+            self.used -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.current as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // how a GIF decoder reads the codes back, counting the clear codes it meets
+    fn unlzw(data: &[u8]) -> (Vec<u8>, usize) {
+        let clear = 1 << MIN_CODE_SIZE;
+        let end = clear + 1;
+        let singles: Vec<Vec<u8>> = (0..clear as u8).map(|index| vec![index]).chain([Vec::new(), Vec::new()]).collect();
+
+        let mut table = singles.clone();
+        let mut size = MIN_CODE_SIZE + 1;
+        let mut at = 0;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut indices = Vec::new();
+        let mut clears = 0;
+        loop {
+            let code = (0..size).fold(0, |code, bit| {
+                let value = code | ((data[at / 8] >> (at % 8)) as usize & 1) << bit;
+                at += 1;
+                value
+            });
+
+            if code == clear {
+                table = singles.clone();
+                size = MIN_CODE_SIZE + 1;
+                previous = None;
+                clears += 1;
+                continue;
+            }
+            if code == end {
+                break;
+            }
+
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                // the code being defined right now: the previous string and its first index
+                (None, Some(previous)) if code == table.len() => [&previous[..], &previous[..1]].concat(),
+                _ => panic!("code {} is not in the table of {}", code, table.len()),
+            };
+            if let Some(previous) = previous {
+                if table.len() < MAX_CODES as usize {
+                    table.push([&previous[..], &entry[..1]].concat());
+                }
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            indices.extend(&entry);
+            previous = Some(entry);
+        }
+
+        assert_eq!(at.div_ceil(8), data.len(), "bytes after the end code");
+        (indices, clears)
+    }
+
+    // the delay and indices of every image in a GIF
+    fn images(gif: &[u8], width: usize, height: usize) -> Vec<(u16, Vec<u8>)> {
+        assert_eq!(gif[..6], *b"GIF89a");
+        // screen descriptor, colour table and the looping extension
+        let mut at = 6 + 7 + 12 + 19;
+        let mut delay = 0;
+        let mut images = Vec::new();
+        loop {
+            match gif[at] {
+                0x21 => {
+                    assert_eq!(gif[at + 1..at + 4], [0xf9, 0x04, 0x00]);
+                    delay = u16::from_le_bytes([gif[at + 4], gif[at + 5]]);
+                    at += 8;
+                },
+                0x2c => {
+                    assert_eq!(gif[at + 5..at + 9], [width as u8, (width >> 8) as u8, height as u8, (height >> 8) as u8]);
+                    assert_eq!(gif[at + 10], MIN_CODE_SIZE);
+                    at += 11;
+                    let mut data = Vec::new();
+                    while gif[at] != 0 {
+                        data.extend(&gif[at + 1..at + 1 + gif[at] as usize]);
+                        at += 1 + gif[at] as usize;
+                    }
+                    at += 1;
+                    images.push((delay, unlzw(&data).0));
+                },
+                0x3b => return images,
+                other => panic!("unexpected block {:#04x}", other),
+            }
+        }
+    }
+
+    fn gif(frames: &[Vec<u8>], width: usize, height: usize) -> Vec<u8> {
+        let mut gif = GifWriter::new(Vec::new(), width as u16, height as u16, &[(0, 0, 0); 4]).unwrap();
+        for frame in frames {
+            gif.frame(frame.clone()).unwrap();
+        }
+        gif.finish().unwrap()
+    }
+
+    #[test]
+    fn short_inputs_decode_back() {
+        // every length up to where the codes grow past 3, 4, 5 and 6 bits
+        for length in 0..200 {
+            let indices: Vec<u8> = (0..length).map(|n| (n * 7 / 3 % 4) as u8).collect();
+            assert_eq!(unlzw(&lzw(&indices)), (indices, 1), "{} indices", length);
+        }
+        assert_eq!(unlzw(&lzw(&[3; 5000])).0, [3; 5000]);
+    }
+
+    #[test]
+    fn a_full_table_starts_over() {
+        // noise has few repeats, so the table fills up a number of times
+        let mut seed = 1u32;
+        let indices: Vec<u8> = (0..100_000).map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8 & 3
+        }).collect();
+
+        let (decoded, clears) = unlzw(&lzw(&indices));
+        assert_eq!(decoded, indices);
+        assert!(clears > 2, "{} clear codes", clears);
+    }
+
+    #[test]
+    fn frames_too_short_to_show_are_merged() {
+        // a 60 Hz frame is 1.67 cs, so a screen changing every frame is shown every other one or so
+        let frames: Vec<Vec<u8>> = (0..60).map(|frame| vec![frame as u8 % 4; 8]).collect();
+        let images = images(&gif(&frames, 4, 2), 4, 2);
+
+        assert!(images.iter().all(|(delay, _)| *delay >= MIN_DELAY as u16), "{:?}", images);
+        assert_eq!(images.iter().map(|(delay, _)| *delay as u64).sum::<u64>(), centiseconds(60));
+        // the screen that lasted is the one shown
+        assert_eq!(images[0], (3, vec![1; 8]));
+    }
+
+    #[test]
+    fn unchanged_frames_become_one_image() {
+        let frames: Vec<Vec<u8>> = (0..90).map(|frame| vec![(frame / 30) as u8; 64 * 32]).collect();
+        let images = images(&gif(&frames, 64, 32), 64, 32);
+
+        assert_eq!(images, [(50, vec![0; 64 * 32]), (50, vec![1; 64 * 32]), (50, vec![2; 64 * 32])]);
+    }
+}
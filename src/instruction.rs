@@ -0,0 +1,143 @@
+use std::fmt;
+
+/// An opcode taken apart once, so running it again only has to match on the
+/// variant. `x` and `y` are register numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    Clear,
+    Return,
+    Lores,
+    Hires,
+    Jump(u16),
+    Call(u16),
+    SkipEqual {x: u8, nn: u8},
+    SkipNotEqual {x: u8, nn: u8},
+    SkipEqualRegister {x: u8, y: u8},
+    SkipNotEqualRegister {x: u8, y: u8},
+    Set {x: u8, nn: u8},
+    Add {x: u8, nn: u8},
+    Move {x: u8, y: u8},
+    Or {x: u8, y: u8},
+    And {x: u8, y: u8},
+    Xor {x: u8, y: u8},
+    AddRegister {x: u8, y: u8},
+    Subtract {x: u8, y: u8},
+    ShiftRight {x: u8, y: u8},
+    SubtractFrom {x: u8, y: u8},
+    ShiftLeft {x: u8, y: u8},
+    SetI(u16),
+    JumpV0(u16),
+    Random {x: u8, nn: u8},
+    Draw {x: u8, y: u8, n: u8},
+    SkipKey {x: u8},
+    SkipNotKey {x: u8},
+    GetDelay {x: u8},
+    WaitKey {x: u8},
+    SetDelay {x: u8},
+    SetSound {x: u8},
+    AddI {x: u8},
+    Font {x: u8},
+    Decimal {x: u8},
+    Store {x: u8},
+    Load {x: u8},
+    // runs as a no-op, like 0NNN machine code calls
+    Unknown(u16),
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Self {
+        let x = (opcode >> 8 & 0xf) as u8;
+        let y = (opcode >> 4 & 0xf) as u8;
+        let n = (opcode & 0xf) as u8;
+        let nn = (opcode & 0xff) as u8;
+        let nnn = opcode & 0xfff;
+
+        match (opcode >> 12, nn) {
+            (0x0, 0xe0) => Instruction::Clear,
+            (0x0, 0xee) => Instruction::Return,
+            (0x0, 0xfe) => Instruction::Lores,
+            (0x0, 0xff) => Instruction::Hires,
+            (0x1, _) => Instruction::Jump(nnn),
+            (0x2, _) => Instruction::Call(nnn),
+            (0x3, _) => Instruction::SkipEqual {x, nn},
+            (0x4, _) => Instruction::SkipNotEqual {x, nn},
+            (0x5, _) => Instruction::SkipEqualRegister {x, y},
+            (0x6, _) => Instruction::Set {x, nn},
+            (0x7, _) => Instruction::Add {x, nn},
+            (0x8, _) => match n {
+                0x0 => Instruction::Move {x, y},
+                0x1 => Instruction::Or {x, y},
+                0x2 => Instruction::And {x, y},
+                0x3 => Instruction::Xor {x, y},
+                0x4 => Instruction::AddRegister {x, y},
+                0x5 => Instruction::Subtract {x, y},
+                0x6 => Instruction::ShiftRight {x, y},
+                0x7 => Instruction::SubtractFrom {x, y},
+                0xe => Instruction::ShiftLeft {x, y},
+                _ => Instruction::Unknown(opcode),
+            },
+            (0x9, _) => Instruction::SkipNotEqualRegister {x, y},
+            (0xa, _) => Instruction::SetI(nnn),
+            (0xb, _) => Instruction::JumpV0(nnn),
+            (0xc, _) => Instruction::Random {x, nn},
+            (0xd, _) => Instruction::Draw {x, y, n},
+            (0xe, 0x9e) => Instruction::SkipKey {x},
+            (0xe, 0xa1) => Instruction::SkipNotKey {x},
+            (0xf, 0x07) => Instruction::GetDelay {x},
+            (0xf, 0x0a) => Instruction::WaitKey {x},
+            (0xf, 0x15) => Instruction::SetDelay {x},
+            (0xf, 0x18) => Instruction::SetSound {x},
+            (0xf, 0x1e) => Instruction::AddI {x},
+            (0xf, 0x29) => Instruction::Font {x},
+            (0xf, 0x33) => Instruction::Decimal {x},
+            (0xf, 0x55) => Instruction::Store {x},
+            (0xf, 0x65) => Instruction::Load {x},
+            _ => Instruction::Unknown(opcode),
+        }
+    }
+}
+
+/// The disassembly that goes in the trace.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Clear => write!(f, "{:-10}", "CLS"),
+            Instruction::Return => write!(f, "{:-10}", "RTS"),
+            Instruction::Lores => write!(f, "{:-10}", "LORES"),
+            Instruction::Hires => write!(f, "{:-10}", "HIRES"),
+            Instruction::Jump(nnn) => write!(f, "{:-10} ${:03x}", "JUMP", nnn),
+            Instruction::Call(nnn) => write!(f, "{:-10} ${:03x}", "CALL", nnn),
+            Instruction::SkipEqual {x, nn} => write!(f, "{:-10} V{:01x},#${:02x}", "SKIP.EQ", x, nn),
+            Instruction::SkipNotEqual {x, nn} => write!(f, "{:-10} V{:01x},#${:02x}", "SKIP.NE", x, nn),
+            Instruction::SkipEqualRegister {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "SKIP.EQ", x, y),
+            Instruction::SkipNotEqualRegister {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "SKIP.NE", x, y),
+            Instruction::Set {x, nn} => write!(f, "{:-10} V{:01x},#${:02x}", "MVI", x, nn),
+            Instruction::Add {x, nn} => write!(f, "{:-10} V{:01x},#{:02x}", "ADI", x, nn),
+            Instruction::Move {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "MOV.", x, y),
+            Instruction::Or {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "OR.", x, y),
+            Instruction::And {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "AND.", x, y),
+            Instruction::Xor {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "XOR.", x, y),
+            Instruction::AddRegister {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "ADD.", x, y),
+            Instruction::Subtract {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "SUB.", x, y),
+            Instruction::ShiftRight {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "SHR.", x, y),
+            Instruction::SubtractFrom {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "SUBN.", x, y),
+            Instruction::ShiftLeft {x, y} => write!(f, "{:-10} V{:01x},V{:01x}", "SHL.", x, y),
+            Instruction::SetI(nnn) => write!(f, "{:-10} I,#${:03x}", "MVI", nnn),
+            Instruction::JumpV0(nnn) => write!(f, "{:-10} I,#${:03x}(V0)", "JUMP", nnn),
+            Instruction::Random {x, nn} => write!(f, "{:-10} V{:01x}, #${:02x}", "RNDMSK", x, nn),
+            Instruction::Draw {x, y, n} => write!(f, "{:-10} V{:01x}, V{:01x}, #${}", "SPRITE", x, y, n),
+            Instruction::SkipKey {x} => write!(f, "{:-10} V{:01x}", "SKIPKEY.Y", x),
+            Instruction::SkipNotKey {x} => write!(f, "{:-10} V{:01x}", "SKIPKEY.N", x),
+            Instruction::GetDelay {x} => write!(f, "{:-10} V{:01x}, DELAY", "MOV", x),
+            Instruction::WaitKey {x} => write!(f, "{:-10} V{:01x}", "KEY", x),
+            Instruction::SetDelay {x} => write!(f, "{:-10} DELAY,V{:01x}", "MOV", x),
+            Instruction::SetSound {x} => write!(f, "{:-10} SOUND,V{:01x}", "MOV", x),
+            Instruction::AddI {x} => write!(f, "{:-10} I,V{:01x}", "ADI", x),
+            Instruction::Font {x} => write!(f, "{:-10} I,V{:01x}", "FONT", x),
+            Instruction::Decimal {x} => write!(f, "{:-10} (I),V{:01x}", "MOVBCD", x),
+            Instruction::Store {x} => write!(f, "{:-10} I,V0-V{:01x}", "MOVM", x),
+            Instruction::Load {x} => write!(f, "{:-10} V0-V{:01x},(I)", "MOVM", x),
+            Instruction::Unknown(opcode) => write!(f, "Unknown {:x}", opcode >> 12),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+
+// deeper than any request needs, shallow enough that parsing cannot run out of stack
+const MAX_DEPTH: usize = 128;
+
+/// Just enough JSON for the remote control: requests are parsed into this
+/// and responses built from it and written out with `to_string`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    // kept in order, so responses come out the way they were built
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {text: text.as_bytes(), at: 0, depth: 0};
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.at < parser.text.len() {
+            return Err(format!("unexpected {} after the value", parser.text[parser.at] as char));
+        }
+        Ok(value)
+    }
+
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    /// The value of a field, when this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// A whole number that is not negative.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Json::Number(number) if number >= 0.0 && number.fract() == 0.0 && number < u64::MAX as f64 => Some(number as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<u64> for Json {
+    fn from(number: u64) -> Self {
+        Json::Number(number as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Self {
+        Json::String(text.to_string())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(text) => write_string(f, text),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            },
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    // runs of characters that need no escaping go out in one write, a framebuffer is mostly that
+    let mut plain = 0;
+    for (at, c) in text.char_indices() {
+        if c != '"' && c != '\\' && c >= ' ' {
+            continue;
+        }
+        f.write_str(&text[plain..at])?;
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            c => write!(f, "\\u{:04x}", c as u32)?,
+        }
+        plain = at + c.len_utf8();
+    }
+    f.write_str(&text[plain..])?;
+    write!(f, "\"")
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+    // arrays and objects the parser is inside of
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while self.text.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.whitespace();
+        match self.text.get(self.at) {
+            Some(&next) if next == byte => {
+                self.at += 1;
+                Ok(())
+            },
+            Some(&next) => Err(format!("expected {} but got {}", byte as char, next as char)),
+            None => Err(format!("expected {} but the text ended", byte as char)),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        if self.text[self.at..].starts_with(word.as_bytes()) {
+            self.at += word.len();
+            Ok(value)
+        } else {
+            Err(format!("unknown word at {}", self.at))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        if matches!(self.text.get(self.at), Some(b'[' | b'{')) {
+            if self.depth == MAX_DEPTH {
+                return Err(format!("nested more than {} deep at {}", MAX_DEPTH, self.at));
+            }
+            self.depth += 1;
+            let value = self.nested();
+            self.depth -= 1;
+            return value;
+        }
+
+        match self.text.get(self.at) {
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.at;
+                while self.text.get(self.at).is_some_and(|byte| b"+-.eE0123456789".contains(byte)) {
+                    self.at += 1;
+                }
+                let number = std::str::from_utf8(&self.text[start..self.at]).unwrap_or("");
+                number.parse().map(Json::Number).map_err(|_| format!("{} is not a number", number))
+            },
+            Some(&byte) => Err(format!("unexpected {} at {}", byte as char, self.at)),
+            None => Err("the text ended before a value".to_string()),
+        }
+    }
+
+    // an array or object, which value has already counted towards the depth
+    fn nested(&mut self) -> Result<Json, String> {
+        match self.text.get(self.at) {
+            Some(b'[') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                self.whitespace();
+                if self.text.get(self.at) == Some(&b']') {
+                    self.at += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.whitespace();
+                    if self.text.get(self.at) == Some(&b',') {
+                        self.at += 1;
+                    } else {
+                        self.expect(b']')?;
+                        return Ok(Json::Array(values));
+                    }
+                }
+            },
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.whitespace();
+                if self.text.get(self.at) == Some(&b'}') {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    self.whitespace();
+                    if self.text.get(self.at) == Some(&b',') {
+                        self.at += 1;
+                    } else {
+                        self.expect(b'}')?;
+                        return Ok(Json::Object(fields));
+                    }
+                }
+            },
+            _ => unreachable!("value only calls this on [ or {{"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = *self.text.get(self.at).ok_or("the text ended in a string")?;
+            self.at += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self.text.get(self.at).ok_or("the text ended in a string")?;
+                    self.at += 1;
+                    let c = match escaped {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let hex = self.text.get(self.at..self.at + 4).ok_or("the text ended in a string")?;
+                            self.at += 4;
+                            let code = std::str::from_utf8(hex).ok().and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            // surrogate pairs are not needed for anything the server is sent
+                            code.and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        },
+                        other => other as char,
+                    };
+                    bytes.extend(c.to_string().bytes());
+                },
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "a string is not UTF-8".to_string())
+    }
+}
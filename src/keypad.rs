@@ -0,0 +1,109 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use std::time::{Duration, Instant};
+
+// keys laid out as on the COSMAC VIP hex keypad
+const LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xc],
+    [0x4, 0x5, 0x6, 0xd],
+    [0x7, 0x8, 0x9, 0xe],
+    [0xa, 0x0, 0xb, 0xf],
+];
+
+const HIGHLIGHT: Duration = Duration::from_millis(150);
+
+pub struct KeypadOverlay {
+    pub enabled: bool,
+    pub clicked: Option<u8>,
+    left: i32,
+    key_size: i32,
+    polled: [Option<Instant>; 16],
+}
+
+impl KeypadOverlay {
+    /// An overlay beside a game screen of the given size, laid out from the
+    /// start so a click before the first frame lands on the right key.
+    pub fn new(enabled: bool, screen_width: u32, screen_height: u32) -> Self {
+        let mut keypad = KeypadOverlay {
+            enabled,
+            clicked: None,
+            left: 0,
+            key_size: 1,
+            polled: [None; 16],
+        };
+        keypad.layout(screen_width, screen_height);
+        keypad
+    }
+
+    /// The overlay is square and as tall as the game screen, which it sits
+    /// to the right of.
+    pub fn width(&self, screen_height: u32) -> u32 {
+        if self.enabled {screen_height} else {0}
+    }
+
+    pub fn layout(&mut self, screen_width: u32, screen_height: u32) {
+        self.left = screen_width as i32;
+        self.key_size = screen_height as i32 / 4;
+    }
+
+    /// Remembers which keys the game asked for, bit n standing for key n,
+    /// so they can be highlighted.
+    pub fn poll(&mut self, keys: u16) {
+        for (key, polled) in self.polled.iter_mut().enumerate() {
+            if keys & (1 << key) != 0 {
+                *polled = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Returns the key under the given point, in chip-8 pixels.
+    pub fn key_at(&self, x: i32, y: i32) -> Option<u8> {
+        if !self.enabled || x < self.left || y < 0 {
+            return None;
+        }
+
+        let col = (x - self.left) / self.key_size;
+        let row = y / self.key_size;
+        LAYOUT.get(row as usize)?.get(col as usize).copied()
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas<Window>, font: &[u8], pressed: [bool; 16]) {
+        for (row, keys) in LAYOUT.iter().enumerate() {
+            for (col, &key) in keys.iter().enumerate() {
+                let x = self.left + col as i32 * self.key_size;
+                let y = row as i32 * self.key_size;
+                let polled = self.polled[key as usize]
+                    .is_some_and(|at| at.elapsed() < HIGHLIGHT);
+
+                let (background, digit) = if pressed[key as usize] || self.clicked == Some(key) {
+                    (Color::RGB(0xe0, 0xe0, 0xe0), Color::BLACK)
+                } else if polled {
+                    (Color::RGB(0x80, 0x50, 0x00), Color::WHITE)
+                } else {
+                    (Color::RGB(0x20, 0x20, 0x20), Color::RGB(0xa0, 0xa0, 0xa0))
+                };
+
+                let cell = Rect::new(x, y, self.key_size as u32, self.key_size as u32);
+                canvas.set_draw_color(background);
+                canvas.fill_rect(cell).unwrap();
+                canvas.set_draw_color(Color::BLACK);
+                canvas.draw_rect(cell).unwrap();
+
+                // label the key with its sprite from the built-in font
+                canvas.set_draw_color(digit);
+                let unit = self.key_size / 8;
+                let sprite = &font[key as usize * 5..key as usize * 5 + 5];
+                for (dy, byte) in sprite.iter().enumerate() {
+                    for dx in 0..4 {
+                        if byte & (0x80 >> dx) != 0 {
+                            let dot = Rect::new(x + (2 + dx) * unit, y + (2 + dy as i32) * unit, unit as u32, unit as u32);
+                            canvas.fill_rect(dot).unwrap();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! The chip-8 core and everything around it that does not need SDL: the
+//! screen, debugging tools and image and video output. The emulator binary
+//! adds the window, the terminal frontend and the command line on top.
+
+pub mod batch;
+pub mod blocks;
+pub mod chip8;
+pub mod coverage;
+pub mod environment;
+pub mod framebuffer;
+pub mod gif;
+pub mod instruction;
+pub mod json;
+pub mod movie;
+pub mod palette;
+pub mod png;
+pub mod profile;
+pub mod recorder;
+pub mod remote;
+pub mod timing;
+pub mod trace;
+pub mod watch;
@@ -1,148 +1,458 @@
 extern crate sdl2;
-use rand::Rng;
-use sdl2::EventPump;
+mod display;
+mod keypad;
+mod options;
+#[cfg(unix)]
+mod terminal;
+
+use chip8_emulator::{batch, blocks, chip8, coverage, framebuffer, movie, palette, png, profile, recorder, remote, timing, trace, watch};
+
+use chip8::Chip8State;
+use coverage::Coverage;
+use display::Display;
+use framebuffer::Framebuffer;
+use keypad::KeypadOverlay;
+use movie::Movie;
+use options::Options;
+use profile::Profiler;
+use recorder::{Format, Recorder};
+use remote::Server;
 use sdl2::event::Event;
 use sdl2::keyboard::Scancode;
-use sdl2::pixels::PixelFormatEnum;
-use sdl2::render::Canvas;
-use sdl2::render::Texture;
-use sdl2::video::Window;
+use sdl2::mouse::MouseButton;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
+use std::fs;
 use std::io;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
 use std::io::SeekFrom;
 use std::io::Seek;
+use std::mem;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use timing::Timing;
+use trace::Tracer;
+use watch::Watcher;
 
 fn main() -> io::Result<()>{
+    let args: Vec<String> = env::args().collect();
+    let options = Options::parse(&args)?;
+    if options.batch {
+        return run_batch(&options);
+    }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        if !options.rom_path.is_empty() {
+            let f = File::open(&options.rom_path)?;
+            let mut reader = BufReader::new(f);
+
+            reader.seek(SeekFrom::End(0))?;
+            reader.rewind()?;
+            reader.read_to_end(&mut buffer)?;
+        }
+        let mut chip8 = Chip8State::new([0;1024*4], Framebuffer::new(), 
+            [0; 16], 0x0);    
+
+        chip8.load_rom(&buffer)?;
+        chip8.display_wait = options.display_wait;
+        chip8.timing = options.timing;
+        chip8.backend = options.backend;
+        if let Some(path) = &options.trace {
+            chip8.tracer = Some(Tracer::create(path, options.trace_format, options.trace_filter.clone())?);
+        }
+        if options.profile.is_some() {
+            chip8.profiler = Some(match options.timing {
+                Timing::Ipf(ipf) => Profiler::new(options.profile_budget.unwrap_or(ipf), "instructions"),
+                Timing::Vip => Profiler::new(options.profile_budget.unwrap_or(timing::VIP_BUDGET), "cycles"),
+            });
+        }
+        if options.coverage.is_some() {
+            chip8.coverage = Some(Coverage::default());
+        }
+        if options.watch || options.watch_break {
+            chip8.watcher = Some(Watcher::new(options.watch_break));
+        }
+        // a movie brings its own seed, a recording needs one to write down
+        if let Some(path) = &options.movie {
+            let movie = Movie::load(path)?;
+            chip8.seed(movie.seed);
+            chip8.movie = Some(movie);
+        } else if options.seed.is_some() || options.record_movie.is_some() {
+            let seed = options.seed.unwrap_or_else(rand::random);
+            chip8.seed(seed);
+            if options.record_movie.is_some() {
+                chip8.movie = Some(Movie::record(seed));
+            }
+        }
+
+    let mut server = match &options.remote {
+        Some(address) => {
+            let server = Server::bind(address)?;
+            eprintln!("remote control on {}", server.address());
+            Some(server)
+        },
+        None => None,
+    };
+
+    if options.benchmark {
+        let result = run_benchmark(&options, &mut chip8);
+        finish_reports(&options, &mut chip8)?;
+        return result;
+    }
+
+    if let (true, Some(server)) = (options.headless, &mut server) {
+        let result = run_remote(&options, &mut chip8, server);
+        finish_reports(&options, &mut chip8)?;
+        return result;
+    }
+
+    if options.headless {
+        // the reports are still written when the program faults, they help find out why
+        let result = run_headless(&options, &mut chip8);
+        finish_reports(&options, &mut chip8)?;
+        return result;
+    }
+
+    if options.tty {
+        #[cfg(unix)]
+        {
+            let result = terminal::run(&options, &mut chip8);
+            finish_reports(&options, &mut chip8)?;
+            return result;
+        }
+        #[cfg(not(unix))]
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "--tty needs a unix terminal"));
+    }
 
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
-    let window = video_subsystem.window("chip8-emulator",64 * 20, 32 * 20)
+    let (screen_width, screen_height) = (framebuffer::WIDTH as u32, framebuffer::HEIGHT as u32);
+    let keypad = KeypadOverlay::new(options.keypad, screen_width, screen_height);
+    let window_width = screen_width + keypad.width(screen_height);
+    let window = video_subsystem.window("chip8-emulator", window_width * options.scale, screen_height * options.scale)
         .position_centered()
+        .resizable()
         .build()
         .unwrap();
 
         let mut event_pump = sdl_context.event_pump().unwrap();
-        let mut canvas = window.into_canvas().build().unwrap();
-
+        let canvas = window.into_canvas().build().unwrap();
         let creator = canvas.texture_creator();
-        canvas.set_scale(20.0, 20.0).unwrap();
-        let mut texture = creator
-        .create_texture_target(PixelFormatEnum::RGB24, 64, 32).unwrap();
+        let mut display = Display::new(canvas, &creator, keypad, options.palette, options.flicker, options.fade);
+        
+    let frame = Duration::from_nanos(1_000_000_000 / 60);
+    let mut next_frame = Instant::now() + frame;
+    let mut recorder = match options.record {
+        Some(_) => Some(start_recording(&options, &chip8)?),
+        None => None,
+    };
 
-        canvas.set_draw_color(sdl2::pixels::Color::BLACK);
-        canvas.clear();
+    'running: loop {
+        let mut keys: HashSet<Scancode> = event_pump
+            .keyboard_state()
+            .pressed_scancodes()
+            .collect();
+        
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit {..} => break 'running,
+                Event::MouseButtonDown {mouse_btn: MouseButton::Left, x, y, ..} => {
+                    display.keypad.clicked = display.keypad.key_at(x, y);
+                },
+                Event::MouseButtonUp {mouse_btn: MouseButton::Left, ..} => display.keypad.clicked = None,
+                Event::KeyDown {scancode: Some(Scancode::F11), repeat: false, ..} => display.toggle_fullscreen(),
+                Event::KeyDown {scancode: Some(Scancode::F12), repeat: false, ..} => {
+                    // a screenshot that cannot be written is no reason to stop playing
+                    if let Err(error) = save_screenshot(&options, &chip8) {
+                        eprintln!("could not save the screenshot: {}", error);
+                    }
+                },
+                Event::KeyDown {scancode: Some(Scancode::F5), repeat: false, ..} if chip8.stopped => {
+                    chip8.stopped = false;
+                    display.set_title("chip8-emulator");
+                },
+                Event::KeyDown {scancode: Some(Scancode::F10), repeat: false, ..} => {
+                    // like a screenshot, a recording that goes wrong leaves the game running
+                    match recorder.take() {
+                        Some(recording) => if let Err(error) = recording.finish() {
+                            eprintln!("could not finish the recording: {}", error);
+                        },
+                        None => match start_recording(&options, &chip8) {
+                            Ok(recording) => recorder = Some(recording),
+                            Err(error) => eprintln!("could not start recording: {}", error),
+                        },
+                    }
+                },
+                _ => {},
+            }
+        }
 
-        let args: Vec<String> = env::args().collect();
-        let file_path = &args[1];
+        // a key held down with the mouse counts as its mapped host key
+        if let Some(clicked) = display.keypad.clicked {
+            keys.extend(get_key_map().into_iter().filter(|(_, key)| *key == clicked).map(|(scancode, _)| scancode));
+        }
 
-        let f = File::open(file_path)?;
-        let mut reader = BufReader::new(f);
-        let mut buffer: Vec<u8> = Vec::new();
-        
-        reader.seek(SeekFrom::End(0))?;
-        reader.rewind()?;
-        reader.read_to_end(&mut buffer)?;
-        // chip8-8 puts programs in memory at 0x200
-        let mut chip8 = Chip8State::new([0;1024*4], [0; 64 * 32 * 3], 
-            [0; 16], 0x0);    
+        // remote requests go in between frames, and the keys it holds count along with ours
+        let mut pressed = pressed_keys(&keys);
+        if let Some(server) = &mut server {
+            server.poll(&mut chip8);
+            report_events(&mut chip8);
+            report_server_errors(server);
+            if server.quit {
+                break 'running;
+            }
+            for (key, remote) in pressed.iter_mut().zip(server.keys) {
+                *key |= remote;
+            }
+        }
 
-        chip8.memory[0x200 .. (0x200 + &buffer.len())].copy_from_slice(&buffer[..]);
-        chip8.font = [
-            0xf0, 0x90, 0x90, 0x90, 0xf0, // 0
-            0x20, 0x60, 0x20, 0x20, 0x70, // 1
-            0xf0, 0x10, 0xf0, 0x80, 0xf0, // 2
-            0xf0, 0x10, 0xf0, 0x10, 0xf0, // 3
-            0x90, 0x90, 0xf0, 0x10, 0x10, // 4
-            0xf0, 0x80, 0xf0, 0x10, 0xf0, // 5
-            0xf0, 0x80, 0xf0, 0x90, 0xf0, // 6
-            0xf0, 0x10, 0x20, 0x40, 0x40, // 7
-            0xf0, 0x90, 0xf0, 0x90, 0xf0, // 8
-            0xf0, 0x90, 0xf0, 0x10, 0xf0, // 9
-            0xf0, 0x90, 0xf0, 0x90, 0x90, // A
-            0xe0, 0x90, 0xe0, 0x90, 0xe0, // B
-            0xf0, 0x80, 0x80, 0x80, 0xf0, // C
-            0xe0, 0x90, 0x90, 0x90, 0xe0, // D
-            0xf0, 0x80, 0xf0, 0x80, 0xf0, // E
-            0xf0, 0x80, 0xf0, 0x80, 0x80, // F
-        ];
-
-        chip8.memory[0x0 .. 0x50].copy_from_slice(&chip8.font);
-        
-    'running: loop {
-        canvas.clear();
-        let mut tickrate = 7;
-
-        while (chip8.pc) < 0x200 + buffer.len() as u16{
-            let keys: HashSet<Scancode> = event_pump
-                .keyboard_state()
-                .pressed_scancodes()
-                .collect();
-            
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit {..} => break 'running,
-                    _ => {},
+        if !chip8.stopped {
+            chip8.set_keys(pressed);
+            let result = chip8.run_frame();
+            if let Some(recording) = &mut recorder {
+                if let Err(error) = recording.frame(&chip8.screen) {
+                    eprintln!("recording stopped: {}", error);
+                    recorder = None;
                 }
             }
-            if tickrate == 0 {
-                ::std::thread::sleep(std::time::Duration::new(0, 1666667 as u32));
-                disassemble(&mut chip8, &mut canvas, &mut texture, &mut event_pump, &keys);
-                chip8.pc += 2;
-                print!("\n"); 
-                tickrate = 7;
+            report_events(&mut chip8);
+            match result {
+                // a fault stops the game with the window left open to look at
+                Err(fault) => {
+                    eprintln!("{}", fault);
+                    chip8.stopped = true;
+                    display.set_title(&format!("chip8-emulator - {}", fault));
+                },
+                Ok(()) if chip8.stopped => display.set_title("chip8-emulator - stopped, F5 to carry on"),
+                Ok(()) => {},
             }
-            
-            tickrate -= 1;
+        }
 
-            if chip8.delay > 0 {
-                chip8.delay -= 1;
-            }
+        display.keypad.poll(mem::take(&mut chip8.polled_keys));
+        display.present(&chip8.screen, &chip8.font, pressed);
+
+        // sleep off what is left of the frame, unless we are already behind
+        let now = Instant::now();
+        if now < next_frame {
+            ::std::thread::sleep(next_frame - now);
+            next_frame += frame;
+        } else {
+            next_frame = now + frame;
+        }
+    }
+
+    if let Some(recording) = recorder {
+        recording.finish()?;
+    }
+
+    finish_reports(&options, &mut chip8)
+}
+
+/// Runs the game for a fixed number of frames without opening a window.
+fn run_headless(options: &Options, chip8: &mut Chip8State) -> io::Result<()> {
+    let mut recorder = match options.record {
+        Some(_) => Some(start_recording(options, chip8)?),
+        None => None,
+    };
+
+    for _ in 0..options.frames {
+        // nobody is at the keys, but a movie can be
+        chip8.set_keys([false; 16]);
+        chip8.run_frame()?;
+        if let Some(recording) = &mut recorder {
+            recording.frame(&chip8.screen)?;
+        }
+
+        report_events(chip8);
+        if chip8.stopped {
+            eprintln!("stopped in frame {} at ${:03x}", chip8.frame - 1, chip8.pc);
+            break;
+        }
+    }
+
+    if let Some(recording) = recorder {
+        recording.finish()?;
+    }
+
+    if options.screenshot.is_some() {
+        save_screenshot(options, chip8)?;
+    }
+
+    Ok(())
+}
 
+/// Leaves the game to the `--remote` client: frames only run when it asks,
+/// and it runs until the client says to quit.
+fn run_remote(options: &Options, chip8: &mut Chip8State, server: &mut Server) -> io::Result<()> {
+    while !server.quit {
+        if !server.poll(chip8) {
+            ::std::thread::sleep(Duration::from_millis(1));
         }
+        report_events(chip8);
+        report_server_errors(server);
+    }
+
+    if options.screenshot.is_some() {
+        save_screenshot(options, chip8)?;
     }
 
     Ok(())
 }
 
-struct Chip8State {
-    v: [u8; 16],
-    i: u16,
-    sp: u16,
-    pc: u16,
-    delay: u8,
-    font: [u8; 0x50],
-    memory: [u8; 1024 * 4],
-    screen: [u8; 64 * 32 * 3],
-    stack: Vec<u16>,
+/// Runs the game for a fixed number of frames as fast as it will go, without
+/// a window, and reports how many instructions that came to per second.
+fn run_benchmark(options: &Options, chip8: &mut Chip8State) -> io::Result<()> {
+    let start = Instant::now();
+
+    for _ in 0..options.frames {
+        chip8.set_keys([false; 16]);
+        chip8.run_frame()?;
+        if chip8.stopped {
+            break;
+        }
+    }
+
+    let seconds = start.elapsed().as_secs_f64();
+    println!("{} frames, {} instructions in {:.3}s", chip8.frame, chip8.instructions, seconds);
+    println!("{:.0} instructions per second, {:.0} frames per second",
+        chip8.instructions as f64 / seconds, chip8.frame as f64 / seconds);
+
+    Ok(())
+}
+
+/// Runs every file in the `--batch` directory at once, headless, and prints
+/// how each one ended: the frames it got through, a hash of the final
+/// screen, and whether it ran to the end, halted or crashed.
+fn run_batch(options: &Options) -> io::Result<()> {
+    let movie = match &options.movie {
+        Some(path) => Some(Movie::load(path)?),
+        None => None,
+    };
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&options.rom_path)? {
+        let path = entry?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut jobs = Vec::new();
+    for path in &paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let mut job = batch::Job::new(&name, &fs::read(path)?);
+        job.seed = options.seed.unwrap_or(0);
+        job.frames = options.frames;
+        job.timing = options.timing;
+        job.backend = options.backend;
+        job.display_wait = options.display_wait;
+        job.movie = movie.clone();
+        jobs.push(job);
+    }
+
+    let threads = options.threads
+        .or_else(|| std::thread::available_parallelism().ok().map(|threads| threads.get()))
+        .unwrap_or(1);
+    let outcomes = batch::run(&jobs, threads);
+
+    let width = jobs.iter().map(|job| job.name.len()).max().unwrap_or(0).max(3);
+    println!("{:width$}  {:>8}  {:>12}  {:16}  result", "rom", "frames", "instructions", "screen");
+    for outcome in &outcomes {
+        let result = match (&outcome.fault, outcome.halted()) {
+            (Some(fault), _) => format!("crashed, {}", fault),
+            (None, true) => format!("halted at ${:03x}", outcome.chip8.pc),
+            (None, false) => "ok".to_string(),
+        };
+        println!("{:width$}  {:>8}  {:>12}  {:016x}  {}",
+            outcome.name, outcome.frames(), outcome.chip8.instructions, outcome.screen_hash(), result);
+    }
+
+    let crashed = outcomes.iter().filter(|outcome| outcome.fault.is_some()).count();
+    let halted = outcomes.iter().filter(|outcome| outcome.halted()).count();
+    println!("{} roms: {} ran all {} frames, {} halted, {} crashed",
+        outcomes.len(), outcomes.len() - crashed - halted, options.frames, halted, crashed);
+
+    Ok(())
 }
 
-impl Chip8State {
-    fn new(memory: [u8; 1024 * 4], screen:  [u8; 64 * 32 * 3], 
-        v: [u8;16], delay: u8) -> Self {Chip8State {
-            memory,
-            screen,
-            sp: 0,
-            pc: 0x200,
-            v,
-            font: [0; 0x50],
-            i: 0x0,
-            delay,
-            stack: Vec::with_capacity(16),
+/// Prints what the watcher found since last time.
+fn report_events(chip8: &mut Chip8State) {
+    if let Some(watcher) = &mut chip8.watcher {
+        for event in watcher.take_events() {
+            eprintln!("{}", event);
         }
     }
 }
 
-fn get_codes(chip8_mem: [u8; 4096], pc: usize) -> (u8, u8) {
-    let code0 = chip8_mem[pc];
-    let code1 = chip8_mem[pc + 1];
-    
-    (code0, code1)
+/// Prints the connections the remote control could not take.
+fn report_server_errors(server: &mut Server) {
+    for error in server.take_errors() {
+        eprintln!("remote control: {}", error);
+    }
+}
+
+/// Flushes the trace, reporting any write that failed along the way, and
+/// writes the profile and coverage reports.
+fn finish_reports(options: &Options, chip8: &mut Chip8State) -> io::Result<()> {
+    report_events(chip8);
+    if let Some(tracer) = chip8.tracer.take() {
+        tracer.finish()?;
+    }
+
+    if let (Some(profiler), Some(path)) = (&chip8.profiler, &options.profile) {
+        match path.as_str() {
+            "-" => profiler.report(&mut io::stdout())?,
+            _ => profiler.report(&mut File::create(path)?)?,
+        }
+    }
+
+    if let (Some(coverage), Some(path)) = (&chip8.coverage, &options.coverage) {
+        let (start, end) = (0x200, chip8.rom_end as usize);
+        match path.as_str() {
+            "-" => coverage.report(&mut io::stdout(), chip8.memory(), start, end)?,
+            _ => coverage.report(&mut File::create(path)?, chip8.memory(), start, end)?,
+        }
+    }
+
+    if let (Some(movie), Some(path)) = (&chip8.movie, &options.record_movie) {
+        movie.save(path)?;
+    }
+
+    Ok(())
+}
+
+/// Records to the `--record` path, or next to the game named after the frame.
+fn start_recording(options: &Options, chip8: &Chip8State) -> io::Result<Recorder> {
+    let path = options.record.clone().unwrap_or_else(|| {
+        Path::new(&options.rom_path)
+            .with_extension(format!("{}.gif", chip8.frame))
+            .to_string_lossy()
+            .into_owned()
+    });
+    let format = options.record_format.unwrap_or_else(|| Format::for_path(&path));
+
+    eprintln!("recording to {}", path);
+    Recorder::create(&path, format, options.palette, options.scale, &chip8.screen)
+}
+
+/// Saves to the `--screenshot` path, or next to the game named after the frame.
+fn save_screenshot(options: &Options, chip8: &Chip8State) -> io::Result<()> {
+    let path = options.screenshot.clone().unwrap_or_else(|| {
+        Path::new(&options.rom_path)
+            .with_extension(format!("{}.png", chip8.frame))
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    fs::write(&path, png::screenshot(&chip8.screen, &options.palette, options.scale))?;
+    eprintln!("saved screenshot to {}", path);
+
+    Ok(())
 }
 
 fn get_key_map() -> HashMap<Scancode, u8> {
@@ -169,397 +479,13 @@ fn get_key_map() -> HashMap<Scancode, u8> {
     
 }
 
+fn pressed_keys(keys: &HashSet<Scancode>) -> [bool; 16] {
+    let mut pressed = [false; 16];
 
-fn disassemble(chip8: &mut Chip8State, canvas: &mut Canvas<Window>, texture: &mut Texture, event_pump: &mut EventPump, keys: &HashSet<Scancode>) {
-    // ::std::thread::sleep(std::time::Duration::new(0, 1666667 as u32));
-
-    let pc = chip8.pc as usize;
-    let (code0, code1) = get_codes(chip8.memory, pc);
-    let first_nib = code0 >> 4;
-
-    print!("{:x} {:x} {:x} ", pc, code0, code1);
-
-    match first_nib {
-        0x00 => {
-            match code1 {
-                0xe0 => {
-                    print!("{:-10}", "CLS");
-                    chip8.screen.fill(0);
-                    canvas.clear();
-                    texture.update(None, &chip8.screen, 64 * 3).unwrap();
-                    canvas.copy(&texture, None, None).unwrap();
-                    canvas.present();
-                },
-                0xee => {
-                    print!("{:-10}", "RTS");
-                    chip8.pc = chip8.stack.pop().expect("chip8.stack should not be empty");
-                    chip8.sp -= 1;
-                },
-                _ => print!("Unknown 0"),
-            }
-        },
-        0x01 => {
-            print!("{:-10} ${:01x}{:02x}", "JUMP", code0 & 0xf, code1);
-            chip8.pc = ((code0 & 0xf) as u16) << 8 | code1 as u16;
-            chip8.pc -= 2;
-        },
-        0x02 => {
-            print!("{:-10} ${:01x}{:02x}", "CALL", code0 & 0xf, code1);
-            chip8.sp += 1;
-            chip8.stack.push(chip8.pc);
-            let addr = ((code0 & 0xf) as u16) << 8 | code1 as u16;
-            chip8.pc = addr;
-            chip8.pc -= 2;
-        },
-        0x03 => {
-            print!("{:-10} V{:01x},#${:02x}", "SKIP.EQ", code0 & 0xf, code1);
-
-            if chip8.v[(code0 & 0xf) as usize] == code1 {
-                chip8.pc += 2;
-            }
-        },
-        0x04 => {
-            print!("{:-10} V{:01x},#${:02x}", "SKIP.NE", code0 & 0xf, code1);
-            if chip8.v[(code0 & 0xf) as usize] != code1 {
-                chip8.pc += 2;
-            }
-        },
-        0x05 => {
-            print!("{:-10} V{:01x},V{:01x}", "SKIP.EQ", code0 & 0xf, code1 >> 4);
-            if chip8.v[(code0 & 0xf) as usize] == chip8.v[(code1 >> 4) as usize] {
-                chip8.pc += 2;
-            }
-        },
-        0x06 => {
-            print!("{:-10} V{:01x},#${:02x}", "MVI", code0 & 0xf, code1);
-            chip8.v[(code0 & 0xf) as usize] = code1;
-        },
-        0x07 => {
-            print!("{:-10} V{:01x},#{:02x}", "ADI", code0 & 0xf, code1);
-            chip8.v[(code0 & 0x0f) as usize] = chip8.v[(code0 & 0x0f) as usize].overflowing_add(code1).0;
-        },
-        0x08 => {
-            let last_nib: u8 = code1 & 0xf;
-            match last_nib {
-                0x0 => {
-                    print!("{:-10} V{:01x},V{:01x}", "MOV.", code0 & 0xf, code1 & 0xf);
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code1 >> 4) as usize];
-                },
-                0x1 => {
-                    print!("{:-10} V{:01x},V{:01x}", "OR.", code0 & 0xf, code1 & 0x0f);
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize] | chip8.v[(code1 >> 4) as usize]; 
-                    chip8.v[0xf] = 0;
-                },
-                0x2 => {
-                    print!("{:-10} V{:01x},V{:01x}", "AND.", code0 & 0xf, code1 & 0x0f);
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize] & chip8.v[(code1 >> 4) as usize]; 
-                    chip8.v[0xf] = 0;
-                },
-                0x3 => {
-                    print!("{:-10} V{:01x},V{:01x}", "XOR.", code0 & 0xf, code1 & 0x0f);
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize] ^ chip8.v[(code1 >> 4) as usize]; 
-                    chip8.v[0xf] = 0;
-                },
-                0x4 => {
-                    print!("{:-10} V{:01x},V{:01x}", "ADD.", code0 & 0xf, code1 & 0x0f);
-
-
-                    if chip8.v[(code0 & 0x0f) as usize].overflowing_add(chip8.v[(code1 >> 4) as usize]).1 {
-                        chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize].overflowing_add(chip8.v[(code1 >> 4) as usize]).0;
-                        chip8.v[0xf] = 1;                        
-                    } else {
-                        chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize].overflowing_add(chip8.v[(code1 >> 4) as usize]).0;
-                        chip8.v[0xf] = 0;
-                    }
-                },
-                0x5 => {
-                    print!("{:-10} V{:01x},V{:01x}", "SUB.", code0 & 0xf, code1 & 0x0f);
-
-                    if chip8.v[(code0 & 0xf) as usize] > chip8.v[(code1 >> 4) as usize] {
-                        chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize].overflowing_sub(chip8.v[(code1 >> 4) as usize]).0;
-                        chip8.v[0xf] = 1;
-                    } else {
-                        chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize].overflowing_sub(chip8.v[(code1 >> 4) as usize]).0;
-                        chip8.v[0xf] = 0;
-                    }
-                },
-                0x6 => {
-                    print!("{:-10} V{:01x},V{:01x}", "SHR.", code0 & 0xf, code1 & 0x0f);
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code1 >> 4) as usize];
-
-                    if (chip8.v[(code0 & 0xf) as usize] & 0x1) == 1 {
-                        chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize] >> 1; 
-                        chip8.v[0xf] = 1;
-                    } else {
-                        chip8.v[(code0 & 0xf) as usize] = chip8.v[(code0 & 0xf) as usize] >> 1; 
-                        chip8.v[0xf] = 0;
-                    }
-                    
-                },
-                0x7 => {
-                    print!("{:-10} V{:01x},V{:01x}", "SUBN.", code0 & 0xf, code1 & 0x0f);
-                    
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code1 >> 4) as usize].overflowing_sub(chip8.v[(code0 & 0xf) as usize]).0;
-
-                    if chip8.v[(code1 >> 4) as usize] > chip8.v[(code0 & 0xf) as usize] {
-                        chip8.v[0xf] = 1;
-                    } else {
-                        chip8.v[0xf] = 0;
-                    }
-                },
-                0xe => {
-                    print!("{:-10} V{:01x},V{:01x}", "SHL.", code0 & 0xf, code1 & 0x0f);
-                    
-                    chip8.v[(code0 & 0xf) as usize] = chip8.v[(code1 >> 4) as usize];
-                    
-                    if ((chip8.v[(code0 & 0xf) as usize]) >> 7) == 1 {
-                        chip8.v[(code0 & 0xf) as usize] <<= 1;
-                        chip8.v[0xf] = 1;
-                    } else {
-                        chip8.v[(code0 & 0xf) as usize] <<= 1;
-                        chip8.v[0xf] = 0;
-                    }
-                    
-                },
-                _ => print!("Unknown 8")
-            }
-        }
-        0x09 => {
-            print!("{:-10} V{:01x},V{:01x}", "SKIP.NE", code0 & 0xf, code1 >> 4);
-            if chip8.v[(code0 & 0xf) as usize] != chip8.v[(code1 >> 4) as usize] {
-                chip8.pc += 2;
-            }
-        },
-        0x0a => {
-            chip8.i = ((code0 & 0xf) as u16) << 8 | (code1) as u16; 
-            let address_i: u8 = code0 & 0x0f;
-            print!("{:-10} I,#${:01x}{:02x}", "MVI", address_i, code1);
-        },
-        0x0b => {
-            print!("{:-10} I,#${:01x}{:02x}(V0)", "JUMP", code0 & 0xf, code1);
-            chip8.pc = (((code0 & 0xf) as u16) << 8 | code1 as u16) + chip8.v[0] as u16;
-            chip8.pc -= 2;
-        },
-        0x0c => {
-            print!("{:-10} V{:01x}, #${:02x}", "RNDMSK", code0 & 0xf, code1);
-            let random_byte: u8 = rand::thread_rng().gen_range(0..=255);
-            chip8.v[(code0 & 0xf) as usize] = random_byte & code1;
-        },
-        0x0d => {
-
-            print!("{:-10} V{:01x}, V{:01x}, #${}", "SPRITE", code0 & 0xf, code1 >> 4, code1 & 0xf);
-            let width: u16 = 64;
-            let height: u8 = 32;
-            let mut v_x = chip8.v[(code0 & 0xf) as usize] % width as u8;
-            let mut v_y = chip8.v[(code1 >> 4) as usize] % height;
-            let num_of_bytes = code1 & 0xf;
-            chip8.v[0xf] = 0;
-
-            for b in chip8.i..chip8.i + num_of_bytes as u16 {
-                let mut byte = chip8.memory[b as usize];
-                let mut i: usize = 0;
-
-                while i < 8 {
-                    let pixel = byte >> 7;
-                    byte = byte << 1;
-                    i += 1;
-
-                    let (r, g, b) = if pixel == 1 {sdl2::pixels::Color::WHITE.rgb()} 
-                        else {sdl2::pixels::Color::BLACK.rgb()};
-                    
-                    let index = ((v_x * 3)) as usize + (v_y as usize * (width * 3) as usize);
-            
-                    if (chip8.screen[index] == 255) && (pixel == 1) {
-                        chip8.v[0xf] = 1;
-                    }
-
-                    chip8.screen[index] ^= r;
-                    chip8.screen[index + 1] ^= g;
-                    chip8.screen[index + 2] ^= b;
-                    
-                    v_x = v_x + 1; 
-                    
-                    if v_x as u16 > width - 1 {
-                        break;
-                    }
-                }
-
-                    v_x = chip8.v[(code0 & 0xf) as usize] % width as u8;
-                    v_y += 1;
-                    if v_y > (height - 1) {
-                        break;
-                    }
-                }
-                
-                canvas.clear();
-                texture.update(None, &chip8.screen, 64 * 3).unwrap();
-                canvas.copy(&texture, None, None).unwrap();
-                canvas.present();      
-        },
-        0x0e => {
-            match code1 {
-                0x9e => {
-                    print!("{:-10} v{:01x}", "skipkey.y", code0 & 0xf);
-
-                    let key_map = get_key_map();
-
-                    let scancode_requested = key_map.iter()
-                        .find_map(|(key, val)| if *val == (chip8.v[(code0 & 0xf) as usize]) {Some(key)} else {None});
-                    
-                    if keys.contains(scancode_requested.unwrap()) {
-                        chip8.pc += 2;
-                    }
-                    
-                },
-                0xa1 => {
-                    print!("{:-10} V{:01x}", "SKIPKEY.N", code0 & 0x0f);
-
-                    let key_map = get_key_map();
-
-                    let scancode_requested = key_map.iter()
-                        .find_map(|(key, val)| if *val == (chip8.v[(code0 & 0xf) as usize]) {Some(key)} else {None});
-                    
-                    if !keys.contains(scancode_requested.unwrap()) {
-                        chip8.pc += 2;
-                    }
-
-                },
-                _ => print!("Unknown e")
-            }
-        },
-        0x0f => {
-            match code1 {
-                0x07 => { 
-                    print!("{:-10} V{:01x}, DELAY", "MOV", code0 & 0xf);
-                    chip8.v[(code0 & 0xf) as usize] = chip8.delay;
-                },
-                0x0a => {
-                    print!("{:-10} V{:01x}", "KEY", code0 & 0x0f);
-                    let key_map = get_key_map();
-
-                    'running: loop {
-
-                            for event in event_pump.poll_iter() {
-                                match event {
-                                    Event::KeyDown {scancode: Some(Scancode::Num1), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::Num1).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::Num2), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::Num2).unwrap();
-                                        break 'running;
-                                    } 
-                                    Event::KeyDown {scancode: Some(Scancode::Num3), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::Num3).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::Num4), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::Num4).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::Q), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::Q).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::W), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::W).unwrap();
-                                        break 'running; 
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::E), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::E).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::R), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::R).unwrap();
-                                        break 'running; 
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::A), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::A).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::S), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::S).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::D), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::D).unwrap();
-                                        break 'running; 
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::F), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::F).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::Z), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::Z).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::X), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::X).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::C), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::C).unwrap();
-                                        break 'running;
-                                    },
-                                    Event::KeyDown {scancode: Some(Scancode::V), ..} => {
-                                        chip8.v[(code0 & 0xf) as usize] = *key_map.get(&Scancode::V).unwrap();
-                                        break 'running;
-                                    },
-                                    _ => continue, 
-                                };
-                            }
-                            if chip8.delay > 0 {
-                                chip8.delay -= 1;
-                                println!("DELAY {}", &chip8.delay);
-                            }
-                        }
-                }, 
-                0x15 => {
-                    print!("{:-10} DELAY,V{:01x}", "MOV", code0 & 0x0f);
-                    chip8.delay = chip8.v[(code0 & 0xf) as usize];
-                },
-                0x18 => print!(" SOUND, V MOV"),
-                0x1e => {
-                    print!("{:-10} I,V{:01x}", "ADI", code0 & 0x0f);
-                    chip8.i = chip8.i + chip8.v[(code0 & 0xf) as usize] as u16;
-                },
-                0x29 => {
-                    let v_x = chip8.v[(code0 & 0xf) as usize];
-                    chip8.i = (v_x * 5) as u16;
-                },
-                0x33 => {
-                    print!("{:-10} (I),V{:01x}", "MOVBCD", code0 & 0x0f);
-                    let v_x = chip8.v[(code0 & 0xf) as usize];
-                    let mut num = v_x; 
-                    // store digits of decimal value of v_x in I, I + 1, I + 2
-                    let ones = num % 10;
-                    num /= 10; 
-                    let tens = num % 10;
-                    num /= 10;
-                    let hundreds = num % 10;
-
-                    chip8.memory[chip8.i as usize] = hundreds;
-                    chip8.memory[(chip8.i + 1) as usize] = tens;
-                    chip8.memory[(chip8.i + 2) as usize] = ones;
-                },
-                0x55 => {
-                    print!("{:-10} I,V0-V{:01x}", "MOVM", code0 & 0x0f);
-                    chip8.memory[chip8.i as usize ..= (chip8.i as usize + (code0 & 0xf) as usize)].copy_from_slice(
-                        &chip8.v[0..=(code0 & 0xf) as usize]
-                    );
-                    chip8.i += (code0 & 0xf) as u16 + 1;
-                },
-                0x65 => {
-                    print!("{:-10} V0-V{:01x},(I)", "MOVM", code0 & 0x0f);
-                    chip8.v[0..=(code0 & 0xf) as usize].copy_from_slice(
-                        &chip8.memory[chip8.i as usize ..= (chip8.i as usize + (code0 & 0xf) as usize)]
-                    );
-                    chip8.i += (code0 & 0xf) as u16 + 1;
-                },
-                _ => print!("Unknown f")
-            }
-        },
-        _ => print!("wrong input"),
+    for (scancode, key) in get_key_map() {
+        pressed[key as usize] = keys.contains(&scancode);
     }
 
-}
\ No newline at end of file
+    pressed
+}
+
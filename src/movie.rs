@@ -0,0 +1,114 @@
+use std::fs;
+use std::io::{self, Write};
+
+/// Keys held down frame by frame, along with the random seed, so that a game
+/// plays out the same way every time. Only frames where the keys change are
+/// kept. As text:
+///
+/// ```text
+/// chip8 movie
+/// seed 1234
+/// 0 0000
+/// 30 0010
+/// 45 0000
+/// ```
+///
+/// where each line is a frame and the keys held from then on, bit n for key n.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Movie {
+    pub seed: u64,
+    changes: Vec<(u64, u16)>,
+    // recording takes the keys it is given, playing back replaces them
+    recording: bool,
+}
+
+const HEADER: &str = "chip8 movie";
+
+impl Movie {
+    /// An empty movie that keeps the keys it is given.
+    pub fn record(seed: u64) -> Self {
+        Movie {seed, changes: Vec::new(), recording: true}
+    }
+
+    /// A movie to play back, from its text.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut lines = text.lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(invalid(format!("a movie starts with \"{}\"", HEADER)));
+        }
+        let seed = lines.next()
+            .and_then(|(_, line)| line.strip_prefix("seed "))
+            .and_then(|seed| seed.trim().parse().ok())
+            .ok_or_else(|| invalid("the second line of a movie is \"seed N\"".to_string()))?;
+
+        let mut changes: Vec<(u64, u16)> = Vec::new();
+        for (number, line) in lines {
+            let change = line.split_once(' ').and_then(|(frame, keys)| {
+                Some((frame.parse().ok()?, u16::from_str_radix(keys.trim(), 16).ok()?))
+            });
+            match change {
+                Some(change) if changes.last().is_none_or(|last| last.0 < change.0) => changes.push(change),
+                Some(_) => return Err(invalid(format!("line {}: frames have to go up", number))),
+                None => return Err(invalid(format!("line {}: expected \"FRAME KEYS\", e.g. \"30 0010\"", number))),
+            }
+        }
+
+        Ok(Movie {seed, changes, recording: false})
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        Movie::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{}", HEADER)?;
+        writeln!(out, "seed {}", self.seed)?;
+        for (frame, keys) in &self.changes {
+            writeln!(out, "{} {:04x}", frame, keys)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut text = Vec::new();
+        self.write(&mut text)?;
+        fs::write(path, text)
+    }
+
+    /// The keys held in `frame` when playing back.
+    pub fn keys(&self, frame: u64) -> [bool; 16] {
+        let at = self.changes.partition_point(|&(start, _)| start <= frame);
+        let keys = if at == 0 {0} else {self.changes[at - 1].1};
+
+        std::array::from_fn(|key| keys >> key & 1 == 1)
+    }
+
+    /// The last frame where the keys change.
+    pub fn length(&self) -> u64 {
+        self.changes.last().map_or(0, |&(frame, _)| frame)
+    }
+
+    /// Takes the keys the frontend has for `frame` and returns the ones the
+    /// game should see: the same keys when recording, the movie's when playing.
+    pub fn input(&mut self, frame: u64, keys: [bool; 16]) -> [bool; 16] {
+        if !self.recording {
+            return self.keys(frame);
+        }
+
+        let bits = keys.iter().enumerate().fold(0, |bits, (key, &down)| bits | (down as u16) << key);
+        // keys set twice in one frame, the last ones are what the game sees
+        if self.changes.last().is_some_and(|&(start, _)| start == frame) {
+            self.changes.pop();
+        }
+        if self.changes.last().is_none_or(|&(_, last)| last != bits) {
+            self.changes.push((frame, bits));
+        }
+        keys
+    }
+}
@@ -0,0 +1,283 @@
+use crate::blocks::Backend;
+use crate::display::Flicker;
+use crate::palette::Palette;
+use crate::recorder::Format;
+use crate::timing::Timing;
+use crate::trace::{TraceFilter, TraceFormat};
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+
+const USAGE: &str = "usage: chip8_emulator [--keypad] [--scale N] [--ipf N] [--vip-timing] [--backend interpreter|blocks] [--display-wait] [--flicker MODE] [--fade RATE] [--palette NAME] [--colors #RRGGBB,...] [--tty] [--headless] [--frames N] [--benchmark] [--batch] [--threads N] [--remote PORT|SOCKET] [--screenshot FILE] [--record FILE] [--record-format FORMAT] [--trace FILE] [--trace-format text|json] [--trace-pc START-END] [--trace-ops 0,D,F] [--trace-frames START-END] [--profile FILE] [--profile-budget N] [--coverage FILE] [--watch] [--watch-break] [--movie FILE] [--record-movie FILE] [--seed N] [--config FILE] <rom.ch8 | directory>";
+
+pub struct Options {
+    pub rom_path: String,
+    pub keypad: bool,
+    pub scale: u32,
+    pub timing: Timing,
+    pub backend: Backend,
+    pub display_wait: bool,
+    pub flicker: Flicker,
+    pub fade: f32,
+    pub palette: Palette,
+    pub tty: bool,
+    pub headless: bool,
+    pub frames: u64,
+    pub benchmark: bool,
+    // rom_path is a directory of roms to run side by side
+    pub batch: bool,
+    pub threads: Option<usize>,
+    pub remote: Option<String>,
+    pub screenshot: Option<String>,
+    pub record: Option<String>,
+    pub record_format: Option<Format>,
+    pub trace: Option<String>,
+    pub trace_format: TraceFormat,
+    pub trace_filter: TraceFilter,
+    pub profile: Option<String>,
+    pub profile_budget: Option<u32>,
+    pub coverage: Option<String>,
+    pub watch: bool,
+    pub watch_break: bool,
+    pub movie: Option<String>,
+    pub record_movie: Option<String>,
+    pub seed: Option<u64>,
+}
+
+impl Options {
+    /// Reads options from the command line. Settings from a `--config` file
+    /// are applied first so that command line flags can override them.
+    pub fn parse(args: &[String]) -> io::Result<Self> {
+        let args: Vec<String> = args.iter().skip(1).cloned().collect();
+        let mut all_args = Vec::new();
+
+        if let Some(at) = args.iter().position(|arg| arg == "--config") {
+            let path = args.get(at + 1).ok_or_else(|| invalid(USAGE.to_string()))?;
+            all_args.extend(read_config(path)?);
+        }
+        all_args.extend(args);
+
+        let mut rom_path = None;
+        let mut keypad = false;
+        let mut scale = 20;
+        let mut ipf = 10;
+        let mut vip_timing = false;
+        let mut backend = Backend::default();
+        let mut display_wait = false;
+        let mut flicker = Flicker::Off;
+        let mut fade = 0.25;
+        let mut palette = Palette::default();
+        let mut tty = false;
+        let mut headless = false;
+        let mut frames = 600;
+        let mut benchmark = false;
+        let mut batch = false;
+        let mut threads = None;
+        let mut remote = None;
+        let mut screenshot = None;
+        let mut record = None;
+        let mut record_format = None;
+        let mut trace = None;
+        let mut trace_format = TraceFormat::Text;
+        let mut trace_filter = TraceFilter::default();
+        let mut profile = None;
+        let mut profile_budget = None;
+        let mut coverage = None;
+        let mut watch = false;
+        let mut watch_break = false;
+        let mut movie = None;
+        let mut record_movie = None;
+        let mut seed = None;
+        let mut args = all_args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--keypad" => keypad = true,
+                "--scale" => {
+                    scale = value(&mut args, &arg)?.parse()
+                        .ok()
+                        .filter(|scale| *scale > 0)
+                        .ok_or_else(|| invalid("--scale needs a whole number above 0".to_string()))?;
+                },
+                "--ipf" => {
+                    ipf = value(&mut args, &arg)?.parse()
+                        .map_err(|_| invalid("--ipf needs a whole number".to_string()))?;
+                },
+                "--vip-timing" => vip_timing = true,
+                "--backend" => {
+                    let name = value(&mut args, &arg)?;
+                    backend = Backend::named(&name)
+                        .ok_or_else(|| invalid(format!("unknown backend {}", name)))?;
+                },
+                "--display-wait" => display_wait = true,
+                "--flicker" => {
+                    let name = value(&mut args, &arg)?;
+                    flicker = Flicker::named(&name)
+                        .ok_or_else(|| invalid(format!("unknown flicker mode {}", name)))?;
+                },
+                "--fade" => {
+                    fade = value(&mut args, &arg)?.parse()
+                        .ok()
+                        .filter(|fade| *fade > 0.0 && *fade <= 1.0)
+                        .ok_or_else(|| invalid("--fade needs a number above 0 and at most 1".to_string()))?;
+                },
+                "--tty" => tty = true,
+                "--headless" => headless = true,
+                "--frames" => {
+                    frames = value(&mut args, &arg)?.parse()
+                        .map_err(|_| invalid("--frames needs a whole number".to_string()))?;
+                },
+                "--benchmark" => benchmark = true,
+                "--batch" => batch = true,
+                "--threads" => {
+                    threads = Some(value(&mut args, &arg)?.parse()
+                        .ok()
+                        .filter(|threads| *threads > 0)
+                        .ok_or_else(|| invalid("--threads needs a whole number above 0".to_string()))?);
+                },
+                "--remote" => remote = Some(value(&mut args, &arg)?),
+                "--screenshot" => screenshot = Some(value(&mut args, &arg)?),
+                "--record" => record = Some(value(&mut args, &arg)?),
+                "--record-format" => {
+                    let name = value(&mut args, &arg)?;
+                    record_format = Some(Format::named(&name)
+                        .ok_or_else(|| invalid(format!("unknown recording format {}", name)))?);
+                },
+                "--trace" => trace = Some(value(&mut args, &arg)?),
+                "--trace-format" => {
+                    let name = value(&mut args, &arg)?;
+                    trace_format = TraceFormat::named(&name)
+                        .ok_or_else(|| invalid(format!("unknown trace format {}", name)))?;
+                },
+                "--trace-pc" => {
+                    trace_filter.pc = Some(range(&value(&mut args, &arg)?, |address| u16::from_str_radix(address, 16).ok())
+                        .ok_or_else(|| invalid("--trace-pc needs a hex address or range like 200-2ff".to_string()))?);
+                },
+                "--trace-ops" => {
+                    let mut classes = 0u16;
+                    for class in value(&mut args, &arg)?.split(',') {
+                        let nibble = u8::from_str_radix(class.trim(), 16)
+                            .ok()
+                            .filter(|nibble| *nibble < 16)
+                            .ok_or_else(|| invalid(format!("--trace-ops needs hex digits, got {}", class)))?;
+                        classes |= 1 << nibble;
+                    }
+                    trace_filter.classes = Some(classes);
+                },
+                "--trace-frames" => {
+                    trace_filter.frames = Some(range(&value(&mut args, &arg)?, |frame| frame.parse().ok())
+                        .ok_or_else(|| invalid("--trace-frames needs a frame or range like 60-120".to_string()))?);
+                },
+                "--profile" => profile = Some(value(&mut args, &arg)?),
+                "--profile-budget" => {
+                    profile_budget = Some(value(&mut args, &arg)?.parse()
+                        .map_err(|_| invalid("--profile-budget needs a whole number".to_string()))?);
+                },
+                "--coverage" => coverage = Some(value(&mut args, &arg)?),
+                "--watch" => watch = true,
+                "--watch-break" => watch_break = true,
+                "--movie" => movie = Some(value(&mut args, &arg)?),
+                "--record-movie" => record_movie = Some(value(&mut args, &arg)?),
+                "--seed" => {
+                    seed = Some(value(&mut args, &arg)?.parse()
+                        .map_err(|_| invalid("--seed needs a whole number".to_string()))?);
+                },
+                "--config" => {
+                    args.next();
+                },
+                "--palette" => {
+                    let name = value(&mut args, &arg)?;
+                    palette = Palette::named(&name)
+                        .ok_or_else(|| invalid(format!("unknown palette {}", name)))?;
+                },
+                "--colors" => palette = palette.with_colors(&value(&mut args, &arg)?).map_err(invalid)?,
+                flag if flag.starts_with("--") => return Err(invalid(format!("unknown option {}", flag))),
+                _ => rom_path = Some(arg),
+            }
+        }
+
+        // a remote client can load the program itself
+        let rom_path = match rom_path {
+            Some(rom_path) => rom_path,
+            None if remote.is_some() => String::new(),
+            None => return Err(invalid(USAGE.to_string())),
+        };
+        if movie.is_some() && record_movie.is_some() {
+            return Err(invalid("--movie and --record-movie can not be used together".to_string()));
+        }
+        let timing = if vip_timing {Timing::Vip} else {Timing::Ipf(ipf)};
+
+        Ok(Options {
+            rom_path,
+            keypad,
+            scale,
+            timing,
+            backend,
+            display_wait,
+            flicker,
+            fade,
+            palette,
+            tty,
+            headless,
+            frames,
+            benchmark,
+            batch,
+            threads,
+            remote,
+            screenshot,
+            record,
+            record_format,
+            trace,
+            trace_format,
+            trace_filter,
+            profile,
+            profile_budget,
+            coverage,
+            watch,
+            watch_break,
+            movie,
+            record_movie,
+            seed,
+        })
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> io::Result<String> {
+    args.next().ok_or_else(|| invalid(format!("{} needs a value", flag)))
+}
+
+/// Reads `start-end`, or a single value meaning just that one.
+fn range<T>(text: &str, parse: impl Fn(&str) -> Option<T>) -> Option<RangeInclusive<T>> {
+    let (start, end) = text.split_once('-').unwrap_or((text, text));
+    Some(parse(start.trim())?..=parse(end.trim())?)
+}
+
+/// Turns `key = value` lines into the matching command line flags. Blank
+/// lines and lines starting with `#` are ignored, and `true`/`false` switch
+/// flags that take no value.
+fn read_config(path: &str) -> io::Result<Vec<String>> {
+    let mut args = Vec::new();
+
+    for line in fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| invalid(format!("{}: expected key = value, got {}", path, line)))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        match value {
+            "true" => args.push(format!("--{}", key)),
+            "false" => {},
+            _ => args.extend([format!("--{}", key), value.to_string()]),
+        }
+    }
+
+    Ok(args)
+}
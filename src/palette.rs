@@ -0,0 +1,68 @@
+/// Colours used to present the logical screen. A pixel value is the set of
+/// planes it is lit on, so index 0 is the background, 1 is the first plane,
+/// 2 the second XO-CHIP plane and 3 pixels lit on both planes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub colors: [(u8, u8, u8); 4],
+}
+
+pub const THEMES: [(&str, Palette); 5] = [
+    ("classic", Palette {colors: [(0x00, 0x00, 0x00), (0xff, 0xff, 0xff), (0xaa, 0xaa, 0xaa), (0x55, 0x55, 0x55)]}),
+    ("amber", Palette {colors: [(0x1a, 0x10, 0x00), (0xff, 0xb0, 0x00), (0xb3, 0x6b, 0x00), (0xff, 0xd8, 0x80)]}),
+    ("green", Palette {colors: [(0x00, 0x14, 0x00), (0x33, 0xff, 0x66), (0x1f, 0x99, 0x40), (0xa0, 0xff, 0xb0)]}),
+    ("lcd", Palette {colors: [(0x9b, 0xbc, 0x0f), (0x0f, 0x38, 0x0f), (0x30, 0x62, 0x30), (0x8b, 0xac, 0x0f)]}),
+    ("octo", Palette {colors: [(0x99, 0x66, 0x00), (0xff, 0xcc, 0x00), (0xff, 0x66, 0x00), (0x66, 0x22, 0x00)]}),
+];
+
+impl Default for Palette {
+    fn default() -> Self {
+        THEMES[0].1
+    }
+}
+
+impl Palette {
+    pub fn named(name: &str) -> Option<Palette> {
+        THEMES.iter()
+            .find(|(theme, _)| theme.eq_ignore_ascii_case(name))
+            .map(|(_, palette)| *palette)
+    }
+
+    /// Parses a comma separated list of `#rrggbb` colours. Colours that are
+    /// left out keep their value from `self`.
+    pub fn with_colors(mut self, list: &str) -> Result<Palette, String> {
+        let colors: Vec<&str> = list.split(',').map(str::trim).collect();
+
+        if colors.len() > self.colors.len() {
+            return Err(format!("a palette has at most {} colours", self.colors.len()));
+        }
+
+        for (slot, color) in self.colors.iter_mut().zip(colors) {
+            *slot = parse_hex(color).ok_or_else(|| format!("invalid colour {}", color))?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn rgb(&self, pixel: u8) -> (u8, u8, u8) {
+        self.colors[(pixel & 0x3) as usize]
+    }
+
+    /// Converts logical pixel values into an RGB24 buffer.
+    pub fn apply(&self, screen: impl Iterator<Item = u8>, pixels: &mut [u8]) {
+        for (pixel, rgb) in screen.zip(pixels.chunks_exact_mut(3)) {
+            let (r, g, b) = self.rgb(pixel);
+            rgb.copy_from_slice(&[r, g, b]);
+        }
+    }
+}
+
+fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,340 @@
+use crate::framebuffer::Framebuffer;
+use crate::palette::Palette;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// deflate length and distance codes: base value and number of extra bits
+const LENGTHS: [(u16, u8); 29] = [
+    (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 1), (13, 1),
+    (15, 1), (17, 1), (19, 2), (23, 2), (27, 2), (31, 2), (35, 3), (43, 3), (51, 3), (59, 3),
+    (67, 4), (83, 4), (99, 4), (115, 4), (131, 5), (163, 5), (195, 5), (227, 5), (258, 0),
+];
+const DISTANCES: [(u16, u8); 30] = [
+    (1, 0), (2, 0), (3, 0), (4, 0), (5, 1), (7, 1), (9, 2), (13, 2), (17, 3), (25, 3),
+    (33, 4), (49, 4), (65, 5), (97, 5), (129, 6), (193, 6), (257, 7), (385, 7), (513, 8), (769, 8),
+    (1025, 9), (1537, 9), (2049, 10), (3073, 10), (4097, 11), (6145, 11), (8193, 12), (12289, 12), (16385, 13), (24577, 13),
+];
+const MAX_MATCH: usize = 258;
+const MAX_DISTANCE: usize = 32768;
+
+/// Encodes the screen as a PNG with every chip-8 pixel `scale` pixels wide.
+pub fn screenshot(screen: &Framebuffer, palette: &Palette, scale: u32) -> Vec<u8> {
+    let scale = scale as usize;
+    let (width, height) = (screen.width() * scale, screen.height() * scale);
+    let indices = screen.resized(width, height);
+
+    encode_indexed(width as u32, height as u32, &palette.colors, &indices)
+}
+
+/// Encodes an 8-bit palette image, one index per pixel row by row.
+pub fn encode_indexed(width: u32, height: u32, colors: &[(u8, u8, u8)], indices: &[u8]) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+
+    let mut header = Vec::new();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per index, palette colour type, default compression, filter and interlace
+    header.extend([8, 3, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+
+    let palette: Vec<u8> = colors.iter().flat_map(|&(r, g, b)| [r, g, b]).collect();
+    chunk(&mut png, b"PLTE", &palette);
+
+    // every row starts with filter type 0, the rows are repetitive enough without one
+    let mut raw = Vec::with_capacity(indices.len() + height as usize);
+    for row in indices.chunks(width as usize) {
+        raw.push(0);
+        raw.extend(row);
+    }
+    chunk(&mut png, b"IDAT", &zlib(&raw, width as usize + 1));
+    chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+
+    let mut crc = crc32(0xffff_ffff, kind);
+    crc = crc32(crc, data);
+    png.extend((!crc).to_be_bytes());
+}
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+        }
+    }
+
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+/// Compresses with fixed Huffman codes. Scaled screenshots mostly repeat the
+/// pixel to the left or the row above, so only those two distances are tried.
+fn zlib(data: &[u8], row: usize) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    // final block, fixed Huffman codes
+    bits.write(1, 1);
+    bits.write(1, 2);
+
+    let mut at = 0;
+    while at < data.len() {
+        let (length, distance) = [1, row].iter()
+            .filter(|&&distance| distance <= at && distance <= MAX_DISTANCE)
+            .map(|&distance| (match_length(data, at, distance), distance))
+            .max()
+            .unwrap_or((0, 0));
+
+        if length >= 3 {
+            bits.length(length);
+            bits.distance(distance);
+            at += length;
+        } else {
+            bits.literal(data[at] as u16);
+            at += 1;
+        }
+    }
+    bits.literal(256);
+
+    let mut zlib = vec![0x78, 0x01];
+    zlib.extend(bits.finish());
+    zlib.extend(adler32(data).to_be_bytes());
+
+    zlib
+}
+
+fn match_length(data: &[u8], at: usize, distance: usize) -> usize {
+    data[at..].iter()
+        .zip(&data[at - distance..])
+        .take(MAX_MATCH)
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u32,
+    used: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u8) {
+        for bit in 0..count {
+            self.current |= ((value >> bit) & 1) << self.used;
+            self.used += 1;
+            if self.used == 8 {
+                self.bytes.push(self.current as u8);
+                self.current = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    // Huffman codes go out most significant bit first
+    fn code(&mut self, code: u32, count: u8) {
+        let reversed = code.reverse_bits() >> (32 - count);
+        self.write(reversed, count);
+    }
+
+    fn literal(&mut self, value: u16) {
+        let value = value as u32;
+        match value {
+            0..=143 => self.code(0x30 + value, 8),
+            144..=255 => self.code(0x190 + value - 144, 9),
+            256..=279 => self.code(value - 256, 7),
+            _ => self.code(0xc0 + value - 280, 8),
+        }
+    }
+
+    fn length(&mut self, length: usize) {
+        let code = LENGTHS.iter().rposition(|&(base, _)| base as usize <= length).unwrap();
+        let (base, extra) = LENGTHS[code];
+        self.literal(257 + code as u16);
+        self.write((length - base as usize) as u32, extra);
+    }
+
+    fn distance(&mut self, distance: usize) {
+        let code = DISTANCES.iter().rposition(|&(base, _)| base as usize <= distance).unwrap();
+        let (base, extra) = DISTANCES[code];
+        self.code(code as u32, 5);
+        self.write((distance - base as usize) as u32, extra);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.current as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the chunks of a PNG, checking each CRC against a table-driven one
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        let table: Vec<u32> = (0..256u32).map(|n| {
+            (0..8).fold(n, |c, _| if c & 1 == 1 {0xedb8_8320 ^ (c >> 1)} else {c >> 1})
+        }).collect();
+
+        assert_eq!(png[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut at = 8;
+        while at < png.len() {
+            let length = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+            let body = &png[at + 4..at + 8 + length];
+            let crc = !body.iter().fold(0xffff_ffff, |c, &byte| table[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8));
+            assert_eq!(png[at + 8 + length..at + 12 + length], crc.to_be_bytes(), "{:?} crc", &body[..4]);
+
+            chunks.push((body[..4].try_into().unwrap(), body[4..].to_vec()));
+            at += 12 + length;
+        }
+        chunks
+    }
+
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        at: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bits(&mut self, count: u8) -> u32 {
+            (0..count).fold(0, |value, bit| {
+                let byte = self.bytes[self.at / 8];
+                let value = value | ((byte >> (self.at % 8)) as u32 & 1) << bit;
+                self.at += 1;
+                value
+            })
+        }
+
+        // a fixed Huffman code, read a bit at a time most significant first
+        fn literal(&mut self) -> u16 {
+            let mut code = 0;
+            for length in 1..=9 {
+                code = code << 1 | self.bits(1);
+                let value = match (length, code) {
+                    (7, 0..=0x17) => 256 + code,
+                    (8, 0x30..=0xbf) => code - 0x30,
+                    (8, 0xc0..=0xc7) => 280 + code - 0xc0,
+                    (9, 0x190..=0x1ff) => 144 + code - 0x190,
+                    _ => continue,
+                };
+                return value as u16;
+            }
+            panic!("no fixed Huffman code {:b}", code);
+        }
+    }
+
+    // a zlib stream holding one block of fixed Huffman codes
+    fn inflate(zlib: &[u8]) -> Vec<u8> {
+        let header = (zlib[0] as u16) << 8 | zlib[1] as u16;
+        assert_eq!(header & 0x0f00, 0x0800, "deflate");
+        assert_eq!(header % 31, 0, "header check bits");
+        let mut bits = BitReader {bytes: &zlib[2..zlib.len() - 4], at: 0};
+        assert_eq!(bits.bits(1), 1, "the final block");
+        assert_eq!(bits.bits(2), 1, "fixed Huffman codes");
+
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            match bits.literal() {
+                literal @ 0..=255 => data.push(literal as u8),
+                256 => break,
+                code => {
+                    let (base, extra) = LENGTHS[code as usize - 257];
+                    let length = base as usize + bits.bits(extra) as usize;
+                    let code = bits.bits(5).reverse_bits() >> 27;
+                    let (base, extra) = DISTANCES[code as usize];
+                    let distance = base as usize + bits.bits(extra) as usize;
+                    for _ in 0..length {
+                        data.push(data[data.len() - distance]);
+                    }
+                },
+            }
+        }
+
+        assert_eq!(zlib[zlib.len() - 4..], adler32(&data).to_be_bytes());
+        data
+    }
+
+    // decodes what encode_indexed wrote back to its size, palette and indices
+    fn decode(png: &[u8]) -> (u32, u32, Vec<u8>, Vec<u8>) {
+        let chunks = chunks(png);
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| &kind[..]).collect();
+        assert_eq!(kinds, [b"IHDR", b"PLTE", b"IDAT", b"IEND"]);
+
+        let header = &chunks[0].1;
+        let width = u32::from_be_bytes(header[..4].try_into().unwrap());
+        let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        assert_eq!(header[8..], [8, 3, 0, 0, 0]);
+
+        let raw = inflate(&chunks[2].1);
+        assert_eq!(raw.len(), (width as usize + 1) * height as usize);
+        let mut indices = Vec::new();
+        for row in raw.chunks(width as usize + 1) {
+            assert_eq!(row[0], 0, "filter type");
+            indices.extend(&row[1..]);
+        }
+        (width, height, chunks[1].1.clone(), indices)
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(!crc32(0xffff_ffff, b"123456789"), 0xcbf4_3926);
+        assert_eq!(!crc32(0xffff_ffff, b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        // sums that wrap past the modulus, checked against zlib
+        assert_eq!(adler32(&[0xff; 5553]), 0x8e29_9c8b);
+    }
+
+    #[test]
+    fn screenshots_decode_back() {
+        let mut screen = Framebuffer::new();
+        for (x, y) in [(0, 0), (1, 0), (63, 31), (10, 5), (11, 6), (12, 7)] {
+            screen.flip(0, x, y);
+        }
+        screen.flip(1, 12, 7);
+        let palette = Palette::default();
+
+        let (width, height, colors, indices) = decode(&screenshot(&screen, &palette, 3));
+        assert_eq!((width, height), (192, 96));
+        let expected: Vec<u8> = palette.colors.iter().flat_map(|&(r, g, b)| [r, g, b]).collect();
+        assert_eq!(colors, expected);
+        assert_eq!(indices, screen.resized(192, 96));
+        assert_eq!(indices[7 * 3 * 192 + 12 * 3], 3);
+    }
+
+    #[test]
+    fn every_length_and_literal_decodes_back() {
+        // runs of every length up to past the longest match, and bytes above 143 that take 9 bits
+        let mut indices = Vec::new();
+        for length in 1..300 {
+            indices.extend(std::iter::repeat_n((length % 256) as u8, length));
+        }
+        indices.extend((0..=255).collect::<Vec<u8>>());
+        let width = 97;
+        indices.resize(indices.len().div_ceil(width) * width, 7);
+        let height = (indices.len() / width) as u32;
+
+        let png = encode_indexed(width as u32, height, &[(1, 2, 3)], &indices);
+        assert_eq!(decode(&png).3, indices);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// where chip-8 programs start, which stands in for the main routine
+const ENTRY: u16 = 0x200;
+const TOP: usize = 10;
+
+/// The instruction an opcode encodes, written the usual way with the
+/// operands as letters, e.g. `DXYN` or `FX33`.
+pub fn opcode_name(opcode: u16) -> &'static str {
+    match (opcode >> 12, opcode & 0xf, opcode & 0xff) {
+        (0x0, _, 0xe0) => "00E0",
+        (0x0, _, 0xee) => "00EE",
+        (0x0, _, 0xfe) => "00FE",
+        (0x0, _, 0xff) => "00FF",
+        (0x1, _, _) => "1NNN",
+        (0x2, _, _) => "2NNN",
+        (0x3, _, _) => "3XNN",
+        (0x4, _, _) => "4XNN",
+        (0x5, _, _) => "5XY0",
+        (0x6, _, _) => "6XNN",
+        (0x7, _, _) => "7XNN",
+        (0x8, 0x0, _) => "8XY0",
+        (0x8, 0x1, _) => "8XY1",
+        (0x8, 0x2, _) => "8XY2",
+        (0x8, 0x3, _) => "8XY3",
+        (0x8, 0x4, _) => "8XY4",
+        (0x8, 0x5, _) => "8XY5",
+        (0x8, 0x6, _) => "8XY6",
+        (0x8, 0x7, _) => "8XY7",
+        (0x8, 0xe, _) => "8XYE",
+        (0x9, _, _) => "9XY0",
+        (0xa, _, _) => "ANNN",
+        (0xb, _, _) => "BNNN",
+        (0xc, _, _) => "CXNN",
+        (0xd, _, _) => "DXYN",
+        (0xe, _, 0x9e) => "EX9E",
+        (0xe, _, 0xa1) => "EXA1",
+        (0xf, _, 0x07) => "FX07",
+        (0xf, _, 0x0a) => "FX0A",
+        (0xf, _, 0x15) => "FX15",
+        (0xf, _, 0x18) => "FX18",
+        (0xf, _, 0x1e) => "FX1E",
+        (0xf, _, 0x29) => "FX29",
+        (0xf, _, 0x33) => "FX33",
+        (0xf, _, 0x55) => "FX55",
+        (0xf, _, 0x65) => "FX65",
+        _ => "unknown",
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Routine {
+    // cost of the routine itself, and including the routines it calls
+    own: u64,
+    total: u64,
+}
+
+/// Counts where the time goes: runs per address and per kind of instruction,
+/// and the cost of each subroutine and each frame. Cost is counted in `unit`s,
+/// which are instructions or, with VIP timing, machine cycles.
+pub struct Profiler {
+    addresses: Vec<u64>,
+    opcodes: HashMap<&'static str, u64>,
+    routines: HashMap<u16, Routine>,
+    frames: Vec<u32>,
+    // what each frame would have cost with room for everything it had to run
+    needed: Vec<u32>,
+    current: u32,
+    budget: u32,
+    unit: &'static str,
+}
+
+impl Profiler {
+    /// Frames that needed more than `budget` are reported as over budget.
+    pub fn new(budget: u32, unit: &'static str) -> Self {
+        Profiler {
+            addresses: vec![0; 4096],
+            opcodes: HashMap::new(),
+            routines: HashMap::new(),
+            frames: Vec::new(),
+            needed: Vec::new(),
+            current: 0,
+            budget,
+            unit,
+        }
+    }
+
+    /// Counts the instruction at `pc`. The call stack holds the addresses of the
+    /// `2NNN` instructions that were followed, which give the routines running.
+    pub fn record(&mut self, pc: u16, opcode: u16, cost: u32, stack: &[u16], memory: &[u8]) {
+        self.addresses[(pc & 0xfff) as usize] += 1;
+        *self.opcodes.entry(opcode_name(opcode)).or_default() += 1;
+        self.current += cost;
+
+        let targets: Vec<u16> = stack.iter().map(|&call| {
+            let call = call as usize % memory.len();
+            u16::from_be_bytes([memory[call], memory[(call + 1) % memory.len()]]) & 0xfff
+        }).collect();
+        let innermost = targets.last().copied().unwrap_or(ENTRY);
+        self.routines.entry(innermost).or_default().own += cost as u64;
+
+        let mut running = vec![ENTRY];
+        for target in targets {
+            // a recursive routine only counts once towards its own total
+            if !running.contains(&target) {
+                running.push(target);
+            }
+        }
+        for routine in running {
+            self.routines.entry(routine).or_default().total += cost as u64;
+        }
+    }
+
+    /// Ends the frame. `cut_off` is the cost of the instruction the frame ran
+    /// out of room for, none when the program stopped to wait on its own.
+    pub fn end_frame(&mut self, cut_off: Option<u32>) {
+        self.frames.push(self.current);
+        self.needed.push(self.current + cut_off.unwrap_or(0));
+        self.current = 0;
+    }
+
+    pub fn report(&self, out: &mut impl Write) -> io::Result<()> {
+        let instructions: u64 = self.addresses.iter().sum();
+        let cost = self.routines.get(&ENTRY).map_or(0, |main| main.total);
+        let frames = self.frames.len().max(1) as u64;
+        let percent = |count: u64| count as f64 * 100.0 / instructions.max(1) as f64;
+        let share = |part: u64| part as f64 * 100.0 / cost.max(1) as f64;
+
+        writeln!(out, "{} {} in {} frames, {:.1} per frame (least {}, most {})",
+            cost,
+            self.unit,
+            self.frames.len(),
+            cost as f64 / frames as f64,
+            self.frames.iter().min().unwrap_or(&0),
+            self.frames.iter().max().unwrap_or(&0))?;
+
+        let over: Vec<usize> = (0..self.needed.len()).filter(|&frame| self.needed[frame] > self.budget).collect();
+        write!(out, "{} frames needed more than the budget of {} {}", over.len(), self.budget, self.unit)?;
+        if !over.is_empty() {
+            let first: Vec<String> = over.iter().take(TOP).map(|frame| frame.to_string()).collect();
+            write!(out, ", first {}: {}", first.len(), first.join(" "))?;
+        }
+        writeln!(out)?;
+
+        writeln!(out, "\n{:<14} {:>10} {:>6} {:>10} {:>6}", "routines", "own", "%", "total", "%")?;
+        let mut routines: Vec<(&u16, &Routine)> = self.routines.iter().collect();
+        routines.sort_by(|a, b| b.1.own.cmp(&a.1.own).then(a.0.cmp(b.0)));
+        for (&address, routine) in routines.iter().take(TOP) {
+            let name = if address == ENTRY {"main".to_string()} else {format!("${:03x}", address)};
+            writeln!(out, "  {:<12} {:>10} {:>5.1}% {:>10} {:>5.1}%",
+                name, routine.own, share(routine.own), routine.total, share(routine.total))?;
+        }
+
+        writeln!(out, "\n{:<14} {:>10} {:>6}", "addresses", "count", "%")?;
+        let mut addresses: Vec<(usize, u64)> = self.addresses.iter().copied().enumerate().filter(|(_, count)| *count > 0).collect();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (address, count) in addresses.into_iter().take(TOP) {
+            writeln!(out, "  ${:03x}{:>19} {:>5.1}%", address, count, percent(count))?;
+        }
+
+        writeln!(out, "\n{:<14} {:>10} {:>6}", "instructions", "count", "%")?;
+        let mut opcodes: Vec<(&&str, &u64)> = self.opcodes.iter().collect();
+        opcodes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (name, &count) in opcodes {
+            writeln!(out, "  {:<12} {:>10} {:>5.1}%", name, count, percent(count))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::framebuffer::Framebuffer;
+use crate::gif::GifWriter;
+use crate::palette::Palette;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Gif,
+    // uncompressed 4:4:4 YUV4MPEG2, which ffmpeg and most encoders read
+    Y4m,
+    // bare RGB24 frames with no header
+    Rgb,
+}
+
+impl Format {
+    pub fn named(name: &str) -> Option<Format> {
+        match name {
+            "gif" => Some(Format::Gif),
+            "y4m" => Some(Format::Y4m),
+            "rgb" => Some(Format::Rgb),
+            _ => None,
+        }
+    }
+
+    /// Picks the format from the file extension, falling back to GIF.
+    pub fn for_path(path: &str) -> Format {
+        Path::new(path).extension()
+            .and_then(|extension| Format::named(&extension.to_string_lossy()))
+            .unwrap_or(Format::Gif)
+    }
+}
+
+enum Encoder {
+    Gif(GifWriter<BufWriter<File>>),
+    Raw(Format, BufWriter<File>),
+}
+
+/// Captures one screen per 60 Hz frame. The video keeps the size of the
+/// first frame, later frames in another resolution are stretched to fit.
+pub struct Recorder {
+    width: usize,
+    height: usize,
+    palette: Palette,
+    encoder: Encoder,
+}
+
+impl Recorder {
+    pub fn create(path: &str, format: Format, palette: Palette, scale: u32, screen: &Framebuffer) -> io::Result<Self> {
+        let (width, height) = (screen.width() * scale as usize, screen.height() * scale as usize);
+        let mut out = BufWriter::new(File::create(path)?);
+
+        let encoder = match format {
+            Format::Gif => Encoder::Gif(GifWriter::new(out, width as u16, height as u16, &palette.colors)?),
+            Format::Y4m => {
+                writeln!(out, "YUV4MPEG2 W{} H{} F60:1 Ip A1:1 C444", width, height)?;
+                Encoder::Raw(format, out)
+            },
+            Format::Rgb => Encoder::Raw(format, out),
+        };
+
+        Ok(Recorder {
+            width,
+            height,
+            palette,
+            encoder,
+        })
+    }
+
+    pub fn frame(&mut self, screen: &Framebuffer) -> io::Result<()> {
+        let indices = screen.resized(self.width, self.height);
+
+        match &mut self.encoder {
+            Encoder::Gif(gif) => gif.frame(indices),
+            Encoder::Raw(Format::Y4m, out) => {
+                out.write_all(b"FRAME\n")?;
+                // planar: all of Y, then all of U, then all of V
+                for plane in 0..3 {
+                    let colors = self.palette.colors.map(|color| yuv(color)[plane]);
+                    let bytes: Vec<u8> = indices.iter().map(|&index| colors[index as usize]).collect();
+                    out.write_all(&bytes)?;
+                }
+                Ok(())
+            },
+            Encoder::Raw(_, out) => {
+                let mut pixels = vec![0; indices.len() * 3];
+                self.palette.apply(indices.into_iter(), &mut pixels);
+                out.write_all(&pixels)
+            },
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self.encoder {
+            Encoder::Gif(gif) => gif.finish().map(|_| ()),
+            Encoder::Raw(_, mut out) => out.flush(),
+        }
+    }
+}
+
+// BT.601 studio swing, what Y4M players assume
+fn yuv((r, g, b): (u8, u8, u8)) -> [u8; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+
+    [
+        16.0 + (65.738 * r + 129.057 * g + 25.064 * b) / 256.0,
+        128.0 + (-37.945 * r - 74.494 * g + 112.439 * b) / 256.0,
+        128.0 + (112.439 * r - 94.154 * g - 18.285 * b) / 256.0,
+    ].map(|channel| channel.round() as u8)
+}
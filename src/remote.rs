@@ -0,0 +1,428 @@
+use crate::chip8::Chip8State;
+use crate::json::Json;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+// JSON-RPC's own error codes, and one for the machine faulting
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const FAULT: i64 = -32000;
+
+// a request line longer than this gets an error and the connection dropped
+const MAX_LINE: usize = 1 << 20;
+// and so does a client that leaves this much of its responses unread
+const MAX_OUTPUT: usize = 16 << 20;
+
+trait Stream: Read + Write {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Stream for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, String),
+}
+
+struct Connection {
+    stream: Box<dyn Stream>,
+    // what has come in since the last full line
+    input: Vec<u8>,
+    // responses the socket has not taken yet
+    output: Vec<u8>,
+}
+
+/// Lets other programs drive the emulator over a local socket, with one
+/// JSON-RPC 2.0 request per line and one response line for each request
+/// but a notification, one with no id. The frontend calls `poll` between
+/// frames, so requests never run in the middle of one.
+///
+/// Methods, with their params:
+///
+/// ```text
+/// load_rom        {"path": FILE} or {"rom": HEX}, "seed" optional   starts over with a new program
+/// step            {"count": N}         runs N instructions with the remote keys, 1 by default, and gives the registers
+/// run_frames      {"count": N}         runs N frames with the remote keys, 1 by default
+/// press_key       {"key": K}           holds key K down until release_key
+/// release_key     {"key": K}
+/// read_memory     {"address": A, "length": N}
+/// write_memory    {"address": A, "bytes": [...]}
+/// get_registers
+/// set_registers   {"v": [...], "i": I, "pc": PC, "delay": D}, all optional
+/// get_framebuffer                      the screen as rows of hex digits, one a pixel
+/// quit
+/// ```
+pub struct Server {
+    listener: Listener,
+    connections: Vec<Connection>,
+    // held down from here, on top of any keys the frontend has down
+    pub keys: [bool; 16],
+    // set by quit, for the frontend to stop
+    pub quit: bool,
+    // connections that could not be taken, for the frontend to report
+    errors: Vec<io::Error>,
+}
+
+impl Server {
+    /// Listens on `address`: a port number for TCP on localhost, or a path
+    /// for a Unix socket.
+    pub fn bind(address: &str) -> io::Result<Self> {
+        let listener = match address.parse::<u16>() {
+            Ok(port) => Listener::Tcp(TcpListener::bind(("127.0.0.1", port))?),
+            #[cfg(unix)]
+            Err(_) => {
+                // left over from a run that did not get to clean up
+                if fs::metadata(address).is_ok_and(|metadata| is_socket(&metadata)) {
+                    fs::remove_file(address)?;
+                }
+                Listener::Unix(UnixListener::bind(address)?, address.to_string())
+            },
+            #[cfg(not(unix))]
+            Err(_) => return Err(io::Error::new(io::ErrorKind::Unsupported, "unix sockets need a unix system, give a port")),
+        };
+
+        match &listener {
+            Listener::Tcp(listener) => listener.set_nonblocking(true)?,
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => listener.set_nonblocking(true)?,
+        }
+
+        Ok(Server {listener, connections: Vec::new(), keys: [false; 16], quit: false, errors: Vec::new()})
+    }
+
+    /// The TCP port, which is only known here when bound to port 0.
+    pub fn port(&self) -> Option<u16> {
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr().ok().map(|address| address.port()),
+            #[cfg(unix)]
+            Listener::Unix(..) => None,
+        }
+    }
+
+    pub fn address(&self) -> String {
+        match &self.listener {
+            Listener::Tcp(_) => format!("127.0.0.1:{}", self.port().unwrap_or(0)),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => path.clone(),
+        }
+    }
+
+    /// Takes new connections and answers every full request line waiting
+    /// on them, without blocking. Returns whether there were any.
+    pub fn poll(&mut self, chip8: &mut Chip8State) -> bool {
+        self.accept();
+
+        let mut handled = false;
+        let mut connections = mem::take(&mut self.connections);
+        // a connection that fails is dropped, it is up to the client to connect again
+        connections.retain_mut(|connection| match self.serve(connection, chip8) {
+            Ok((served, open)) => {
+                handled |= served;
+                open
+            },
+            Err(_) => false,
+        });
+        self.connections = connections;
+
+        handled
+    }
+
+    /// Hands over the errors from taking connections since the last call.
+    /// They only cost the connection, the server carries on.
+    pub fn take_errors(&mut self) -> Vec<io::Error> {
+        mem::take(&mut self.errors)
+    }
+
+    fn accept(&mut self) {
+        loop {
+            let accepted: io::Result<Box<dyn Stream>> = match &self.listener {
+                Listener::Tcp(listener) => listener.accept().map(|(stream, _)| Box::new(stream) as Box<dyn Stream>),
+                #[cfg(unix)]
+                Listener::Unix(listener, _) => listener.accept().map(|(stream, _)| Box::new(stream) as Box<dyn Stream>),
+            };
+
+            match accepted.and_then(|stream| stream.set_nonblocking(true).map(|_| stream)) {
+                Ok(stream) => self.connections.push(Connection {stream, input: Vec::new(), output: Vec::new()}),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return,
+                // out of file descriptors, say, which may well still be so on the next go
+                Err(error) => {
+                    self.errors.push(error);
+                    return;
+                },
+            }
+        }
+    }
+
+    // reads what is there and answers the full lines, and says whether the connection is still open
+    fn serve(&mut self, connection: &mut Connection, chip8: &mut Chip8State) -> io::Result<(bool, bool)> {
+        // the client is done sending, it is kept until it has all its responses
+        let mut finished = false;
+        let mut buffer = [0; 4096];
+        loop {
+            match connection.stream.read(&mut buffer) {
+                Ok(0) => {
+                    finished = true;
+                    break;
+                },
+                Ok(length) => {
+                    connection.input.extend_from_slice(&buffer[..length]);
+                    // the rest can wait until these lines are answered
+                    if connection.input.len() > MAX_LINE {
+                        break;
+                    }
+                },
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+                Err(error) => return Err(error),
+            }
+        }
+
+        let mut served = false;
+        // taken off the input all at once afterwards, not shuffled down a line at a time
+        let mut start = 0;
+        while let Some(length) = connection.input[start..].iter().position(|&byte| byte == b'\n') {
+            let line = String::from_utf8_lossy(&connection.input[start..start + length]);
+            start += length + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            served = true;
+            if let Some(response) = self.respond(line.trim(), chip8) {
+                connection.output.extend(format!("{}\n", response).bytes());
+            }
+        }
+        connection.input.drain(..start);
+
+        // told why, as far as the socket takes it, and dropped
+        let too_long = connection.input.len() > MAX_LINE;
+        if too_long {
+            let message = format!("the request is longer than {} bytes", MAX_LINE);
+            connection.output.extend(format!("{}\n", response(Json::Null, Err((INVALID_REQUEST, message)))).bytes());
+            served = true;
+        }
+
+        // a big framebuffer may not fit in the socket's buffer, what is left goes on the next poll
+        let mut sent = 0;
+        while sent < connection.output.len() {
+            match connection.stream.write(&connection.output[sent..]) {
+                Ok(0) => return Ok((served, false)),
+                Ok(length) => sent += length,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+                Err(error) => return Err(error),
+            }
+        }
+        connection.output.drain(..sent);
+        let open = !finished || !connection.output.is_empty();
+        Ok((served, open && !too_long && connection.output.len() <= MAX_OUTPUT))
+    }
+
+    /// The response to one request line, none for a notification.
+    fn respond(&mut self, line: &str, chip8: &mut Chip8State) -> Option<Json> {
+        let request = match Json::parse(line) {
+            Ok(request) => request,
+            Err(message) => return Some(response(Json::Null, Err((PARSE_ERROR, message)))),
+        };
+        // only an object with a method can be a notification, anything else is answered even without an id
+        let Some(method) = request.get("method").and_then(Json::as_str) else {
+            let id = request.get("id").cloned().unwrap_or(Json::Null);
+            return Some(response(id, Err((INVALID_REQUEST, "the request is not an object with a method".to_string()))));
+        };
+        let params = request.get("params").cloned().unwrap_or(Json::Object(Vec::new()));
+
+        let result = self.call(method, &params, chip8);
+        request.get("id").cloned().map(|id| response(id, result))
+    }
+
+    fn call(&mut self, method: &str, params: &Json, chip8: &mut Chip8State) -> Result<Json, (i64, String)> {
+        match method {
+            "load_rom" => {
+                let rom = match (params.get("path").and_then(Json::as_str), params.get("rom").and_then(Json::as_str)) {
+                    (Some(path), _) => fs::read(path).map_err(|error| (INVALID_PARAMS, format!("{}: {}", path, error)))?,
+                    (None, Some(hex)) => from_hex(hex).ok_or((INVALID_PARAMS, "rom needs to be hex digits".to_string()))?,
+                    (None, None) => return Err((INVALID_PARAMS, "load_rom needs a path or a rom".to_string())),
+                };
+
+                // the frontend's settings and instruments carry over to the new program
+                let mut fresh = Chip8State::builder().build();
+                fresh.load_rom(&rom).map_err(fault)?;
+                if let Some(seed) = params.get("seed") {
+                    fresh.seed(seed.as_u64().ok_or((INVALID_PARAMS, "seed needs to be a whole number".to_string()))?);
+                }
+                fresh.timing = chip8.timing;
+                fresh.backend = chip8.backend;
+                fresh.display_wait = chip8.display_wait;
+                fresh.tracer = chip8.tracer.take();
+                fresh.profiler = chip8.profiler.take();
+                fresh.coverage = chip8.coverage.take();
+                fresh.watcher = chip8.watcher.take();
+                *chip8 = fresh;
+                self.keys = [false; 16];
+
+                Ok(Json::object(vec![("size", (rom.len() as u64).into())]))
+            },
+            "step" => {
+                let count = count(params)?;
+                // once, as run_frames does a frame, so FX0A sees a key pressed since the last call
+                chip8.set_keys(self.keys);
+                for _ in 0..count {
+                    chip8.step().map_err(fault)?;
+                }
+                Ok(registers(chip8))
+            },
+            "run_frames" => {
+                for _ in 0..count(params)? {
+                    chip8.set_keys(self.keys);
+                    chip8.run_frame().map_err(fault)?;
+                }
+                Ok(Json::object(vec![("frame", chip8.frame.into()), ("halted", Json::Bool(chip8.halted()))]))
+            },
+            "press_key" | "release_key" => {
+                let key = number(params, "key", 0xf)?;
+                self.keys[key as usize] = method == "press_key";
+                Ok(Json::Null)
+            },
+            "read_memory" => {
+                let address = number(params, "address", chip8.memory().len() as u64)? as usize;
+                let length = number(params, "length", (chip8.memory().len() - address) as u64)? as usize;
+                let bytes = chip8.memory()[address..address + length].iter().map(|&byte| (byte as u64).into());
+                Ok(Json::Array(bytes.collect()))
+            },
+            "write_memory" => {
+                let address = number(params, "address", chip8.memory().len() as u64)? as usize;
+                let bytes = bytes(params.get("bytes"), chip8.memory().len() - address)?;
+                // through write_memory, so decoded instructions and blocks are dropped
+                chip8.write_memory(address, &bytes);
+                Ok(Json::Null)
+            },
+            "get_registers" => Ok(registers(chip8)),
+            "set_registers" => {
+                if params.get("v").is_some() {
+                    let v = bytes(params.get("v"), 16)?;
+                    chip8.v[..v.len()].copy_from_slice(&v);
+                }
+                if params.get("i").is_some() {
+                    chip8.i = number(params, "i", 0xffff)? as u16;
+                }
+                if params.get("pc").is_some() {
+                    chip8.pc = number(params, "pc", 0xfff)? as u16;
+                }
+                if params.get("delay").is_some() {
+                    chip8.delay = number(params, "delay", 0xff)? as u8;
+                }
+                Ok(registers(chip8))
+            },
+            "get_framebuffer" => {
+                let screen = &chip8.screen;
+                let rows = (0..screen.height()).map(|y| {
+                    Json::String((0..screen.width()).map(|x| char::from(b'0' + screen.pixel(x, y))).collect())
+                });
+                Ok(Json::object(vec![
+                    ("width", (screen.width() as u64).into()),
+                    ("height", (screen.height() as u64).into()),
+                    ("rows", Json::Array(rows.collect())),
+                ]))
+            },
+            "quit" => {
+                self.quit = true;
+                Ok(Json::Null)
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = &self.listener {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_socket(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_socket()
+}
+
+fn response(id: Json, result: Result<Json, (i64, String)>) -> Json {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err((code, message)) => ("error", Json::object(vec![("code", Json::Number(code as f64)), ("message", message.as_str().into())])),
+    };
+    Json::object(vec![("jsonrpc", "2.0".into()), ("id", id), outcome])
+}
+
+fn fault(fault: crate::chip8::Fault) -> (i64, String) {
+    (FAULT, fault.to_string())
+}
+
+// a whole number param no bigger than `most`
+fn number(params: &Json, name: &str, most: u64) -> Result<u64, (i64, String)> {
+    params.get(name)
+        .and_then(Json::as_u64)
+        .filter(|number| *number <= most)
+        .ok_or_else(|| (INVALID_PARAMS, format!("{} needs to be a whole number up to {}", name, most)))
+}
+
+fn count(params: &Json) -> Result<u64, (i64, String)> {
+    match params.get("count") {
+        Some(_) => number(params, "count", u32::MAX as u64),
+        None => Ok(1),
+    }
+}
+
+// an array of at most `most` bytes
+fn bytes(param: Option<&Json>, most: usize) -> Result<Vec<u8>, (i64, String)> {
+    let invalid = || (INVALID_PARAMS, format!("expected an array of up to {} bytes", most));
+    let values = param.and_then(Json::as_array).filter(|values| values.len() <= most).ok_or_else(invalid)?;
+    values.iter()
+        .map(|value| value.as_u64().filter(|byte| *byte <= 0xff).map(|byte| byte as u8).ok_or_else(invalid))
+        .collect()
+}
+
+fn registers(chip8: &Chip8State) -> Json {
+    let list = |values: &mut dyn Iterator<Item = u64>| Json::Array(values.map(Json::from).collect());
+    Json::object(vec![
+        ("pc", (chip8.pc as u64).into()),
+        ("i", (chip8.i as u64).into()),
+        ("sp", (chip8.sp as u64).into()),
+        ("delay", (chip8.delay as u64).into()),
+        ("v", list(&mut chip8.v.iter().map(|&v| v as u64))),
+        ("stack", list(&mut chip8.stack.iter().map(|&address| address as u64))),
+        ("frame", chip8.frame.into()),
+        ("instructions", chip8.instructions.into()),
+        ("halted", Json::Bool(chip8.halted())),
+    ])
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| u8::from_str_radix(&pair.iter().collect::<String>(), 16).ok())
+        .collect()
+}
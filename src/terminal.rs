@@ -0,0 +1,163 @@
+use crate::chip8::Chip8State;
+use crate::framebuffer::Framebuffer;
+use crate::options::Options;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+// terminals only report key presses, so a key counts as held for a while after
+const HOLD_FRAMES: u8 = 10;
+const ESCAPE: u8 = 0x1b;
+const CTRL_C: u8 = 0x03;
+const ENTER: u8 = b'\r';
+
+/// Same layout as the SDL frontend: 1234/qwer/asdf/zxcv.
+fn key_for(byte: u8) -> Option<u8> {
+    match byte.to_ascii_lowercase() {
+        b'1' => Some(0x1),
+        b'2' => Some(0x2),
+        b'3' => Some(0x3),
+        b'4' => Some(0xc),
+        b'q' => Some(0x4),
+        b'w' => Some(0x5),
+        b'e' => Some(0x6),
+        b'r' => Some(0xd),
+        b'a' => Some(0x7),
+        b's' => Some(0x8),
+        b'd' => Some(0x9),
+        b'f' => Some(0xe),
+        b'z' => Some(0xa),
+        b'x' => Some(0x0),
+        b'c' => Some(0xb),
+        b'v' => Some(0xf),
+        _ => None,
+    }
+}
+
+/// Puts the terminal in raw mode until dropped.
+struct RawMode {
+    tty: File,
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable(tty: File) -> io::Result<Self> {
+        let fd = tty.as_raw_fd();
+        let mut original = unsafe {std::mem::zeroed::<libc::termios>()};
+
+        if unsafe {libc::tcgetattr(fd, &mut original)} != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut raw = original;
+        unsafe {libc::cfmakeraw(&mut raw)};
+        // reads return straight away, with whatever has been typed
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+
+        if unsafe {libc::tcsetattr(fd, libc::TCSANOW, &raw)} != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(RawMode {tty, original})
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = self.tty.write_all(b"\x1b[0m\x1b[2J\x1b[H\x1b[?25h");
+        unsafe {libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.original)};
+    }
+}
+
+/// Plays the game in the terminal, two pixels per character cell. It draws to
+/// and reads from /dev/tty so that stdout stays free for `--trace -`.
+pub fn run(options: &Options, chip8: &mut Chip8State) -> io::Result<()> {
+    let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    let mut raw = RawMode::enable(tty)?;
+    raw.tty.write_all(b"\x1b[2J\x1b[?25l")?;
+
+    let frame = Duration::from_nanos(1_000_000_000 / 60);
+    let mut next_frame = Instant::now() + frame;
+    let mut held = [0u8; 16];
+    let mut shown: Option<Framebuffer> = None;
+
+    loop {
+        let mut input = [0; 64];
+        let count = raw.tty.read(&mut input)?;
+        let input = &input[..count];
+
+        // a lone escape is the escape key, anything longer is an escape sequence
+        if input.contains(&CTRL_C) || input == [ESCAPE] {
+            return Ok(());
+        }
+
+        for held in held.iter_mut() {
+            *held = held.saturating_sub(1);
+        }
+        if !input.starts_with(&[ESCAPE]) {
+            for key in input.iter().filter_map(|&byte| key_for(byte)) {
+                held[key as usize] = HOLD_FRAMES;
+            }
+        }
+
+        if chip8.stopped && input.contains(&ENTER) {
+            chip8.stopped = false;
+            raw.tty.write_all(b"\x1b[0m\x1b[2J")?;
+            shown = None;
+        }
+        if !chip8.stopped {
+            chip8.set_keys(held.map(|frames| frames > 0));
+            chip8.run_frame()?;
+            // what the watcher found is printed once the terminal is back to normal
+            if chip8.stopped {
+                let status = format!("\x1b[0m\x1b[{};1Hstopped at ${:03x}, Enter to carry on", chip8.screen.height() / 2 + 1, chip8.pc);
+                raw.tty.write_all(status.as_bytes())?;
+            }
+        }
+
+        if shown.as_ref() != Some(&chip8.screen) {
+            draw(&mut raw.tty, &chip8.screen, options)?;
+            shown = Some(chip8.screen.clone());
+        }
+
+        let now = Instant::now();
+        if now < next_frame {
+            ::std::thread::sleep(next_frame - now);
+            next_frame += frame;
+        } else {
+            next_frame = now + frame;
+        }
+    }
+}
+
+fn draw(tty: &mut File, screen: &Framebuffer, options: &Options) -> io::Result<()> {
+    let mut out = String::from("\x1b[H");
+    let mut colors = None;
+
+    for row in 0..screen.height() / 2 {
+        out.push_str(&format!("\x1b[{};1H", row + 1));
+
+        for x in 0..screen.width() {
+            let top = options.palette.rgb(screen.pixel(x, row * 2));
+            let bottom = options.palette.rgb(screen.pixel(x, row * 2 + 1));
+
+            // a full block needs only the foreground, otherwise the upper half
+            // is the foreground and the lower half shows through as background
+            let (cell, wanted) = if top == bottom {('█', (top, None))} else {('▀', (top, Some(bottom)))};
+            if colors != Some(wanted) {
+                let (fg, bg) = wanted;
+                out.push_str(&format!("\x1b[38;2;{};{};{}m", fg.0, fg.1, fg.2));
+                if let Some(bg) = bg {
+                    out.push_str(&format!("\x1b[48;2;{};{};{}m", bg.0, bg.1, bg.2));
+                }
+                colors = Some(wanted);
+            }
+            out.push(cell);
+        }
+    }
+
+    tty.write_all(out.as_bytes())?;
+    tty.flush()
+}
@@ -0,0 +1,129 @@
+/// How many instructions fit in a 60 Hz frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Timing {
+    // a fixed number of instructions per frame, whatever they are
+    Ipf(u32),
+    // as many as the COSMAC VIP interpreter gets through in a frame
+    Vip,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing::Ipf(10)
+    }
+}
+
+// the VIP's 1802 does one machine cycle per 8 clocks at 1.76 MHz, and the
+// CDP1861 video chip steals a cycle for every byte it shows: 128 lines of 8
+pub const VIP_FRAME_CYCLES: u32 = 3668;
+pub const VIP_DISPLAY_CYCLES: u32 = 128 * 8;
+// what is left for the interpreter once the display interrupt has run
+pub const VIP_BUDGET: u32 = VIP_FRAME_CYCLES - VIP_DISPLAY_CYCLES - VIP_INTERRUPT_CYCLES;
+const VIP_INTERRUPT_CYCLES: u32 = 46;
+// fetching and decoding the two opcode bytes, which every instruction pays
+const VIP_FETCH_CYCLES: u32 = 15;
+// 00E0 is the 1802 routine at $0E0, which zeroes the 256 byte display page
+// from the top down: 4 instructions to set up, 6 for each byte but the last,
+// 4 for the last and the return, at 2 cycles each
+const VIP_CLEAR_CYCLES: u32 = 2 * (4 + 6 * 255 + 4 + 1);
+// each row of a sprite is shifted right one bit at a time, VX & 7 times,
+// before it is XORed into the two display bytes it lands on
+const VIP_ROW_CYCLES: u32 = 68;
+const VIP_SHIFT_CYCLES: u32 = 16;
+
+/// Machine cycles the VIP interpreter takes for an instruction, following the
+/// interpreter listing in RCA's COSMAC VIP Instruction Manual (VIP-311), as
+/// worked through in Laurence Scotford's "Chip-8 on the COSMAC VIP" articles,
+/// and the per-instruction table in Jackson Sommerich's "Chip-8 Instruction
+/// Scheduling and Frequency" (2019). Most instructions cost the same every
+/// time, but 00E0 takes most of a frame, sprites cost more per row and the
+/// further they are from a byte boundary, and FX33 subtracts its way through
+/// every digit. `v` are the registers before the instruction runs.
+pub fn vip_cycles(opcode: u16, v: &[u8; 16]) -> u32 {
+    let x = (opcode >> 8 & 0xf) as usize;
+    let n = (opcode & 0xf) as u32;
+
+    let cycles = match (opcode >> 12, opcode & 0xff) {
+        // getting to the machine code routine, then the routine itself
+        (0x0, 0xe0) => 24 + VIP_CLEAR_CYCLES,
+        (0x0, 0xee) => 10,
+        (0x0, _) => 10,
+        (0x1, _) => 12,
+        (0x2, _) => 26,
+        (0x3, _) | (0x4, _) => 10,
+        (0x5, _) | (0x9, _) => 14,
+        (0x6, _) => 6,
+        (0x7, _) => 10,
+        // the ALU instructions are run by building an 1802 routine on the stack
+        (0x8, _) => 44,
+        (0xa, _) => 12,
+        (0xb, _) => 22,
+        (0xc, _) => 36,
+        (0xd, _) => 26 + n * (VIP_ROW_CYCLES + VIP_SHIFT_CYCLES * (v[x] & 7) as u32),
+        (0xe, _) => 14,
+        (0xf, 0x07) | (0xf, 0x15) | (0xf, 0x18) => 10,
+        (0xf, 0x0a) => 19,
+        (0xf, 0x1e) => 16,
+        (0xf, 0x29) => 16,
+        (0xf, 0x33) => {
+            let digits = v[x] / 100 + v[x] / 10 % 10 + v[x] % 10;
+            80 + 16 * digits as u32
+        },
+        (0xf, 0x55) | (0xf, 0x65) => 14 + 14 * (x as u32 + 1),
+        _ => 10,
+    };
+
+    VIP_FETCH_CYCLES + cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycles(opcode: u16) -> u32 {
+        vip_cycles(opcode, &[0; 16])
+    }
+
+    #[test]
+    fn fixed_costs() {
+        assert_eq!(cycles(0x6012), 21);
+        assert_eq!(cycles(0x7012), 25);
+        assert_eq!(cycles(0x8124), 59);
+        assert_eq!(cycles(0x1200), 27);
+        assert_eq!(cycles(0x2200), 41);
+        assert_eq!(cycles(0x00ee), 25);
+        assert_eq!(cycles(0xa123), 27);
+    }
+
+    #[test]
+    fn clearing_the_screen_takes_most_of_a_frame() {
+        assert_eq!(VIP_CLEAR_CYCLES, 3078);
+        assert_eq!(cycles(0x00e0), 15 + 24 + 3078);
+        assert!(cycles(0x00e0) > VIP_BUDGET);
+    }
+
+    #[test]
+    fn sprites_cost_more_off_a_byte_boundary() {
+        let mut v = [0; 16];
+        assert_eq!(vip_cycles(0xd015, &v), 15 + 26 + 5 * 68);
+
+        v[0] = 3;
+        assert_eq!(vip_cycles(0xd015, &v), 15 + 26 + 5 * (68 + 3 * 16));
+        // only the bit offset counts, not which byte
+        v[0] = 8 * 5 + 3;
+        assert_eq!(vip_cycles(0xd015, &v), 15 + 26 + 5 * (68 + 3 * 16));
+        v[0] = 7;
+        assert_eq!(vip_cycles(0xd01f, &v), 15 + 26 + 15 * (68 + 7 * 16));
+        // the offset comes from VX, not VY
+        assert_eq!(vip_cycles(0xd105, &v), 15 + 26 + 5 * 68);
+    }
+
+    #[test]
+    fn bcd_and_register_moves() {
+        let mut v = [0; 16];
+        v[2] = 199;
+        assert_eq!(vip_cycles(0xf233, &v), 15 + 80 + 16 * 19);
+        assert_eq!(vip_cycles(0xf355, &v), 15 + 14 + 14 * 4);
+        assert_eq!(vip_cycles(0xf065, &v), 15 + 14 + 14);
+    }
+}
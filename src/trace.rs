@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraceFormat {
+    Text,
+    // one JSON object per line
+    Json,
+}
+
+impl TraceFormat {
+    pub fn named(name: &str) -> Option<TraceFormat> {
+        match name {
+            "text" => Some(TraceFormat::Text),
+            "json" => Some(TraceFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Which instructions end up in the trace. Every filter that is set has to match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceFilter {
+    pub pc: Option<RangeInclusive<u16>>,
+    // bit n is set to trace opcodes whose first nibble is n
+    pub classes: Option<u16>,
+    pub frames: Option<RangeInclusive<u64>>,
+}
+
+impl TraceFilter {
+    pub fn matches(&self, frame: u64, pc: u16, opcode: u16) -> bool {
+        self.pc.as_ref().is_none_or(|range| range.contains(&pc))
+            && self.classes.is_none_or(|classes| classes & (1 << (opcode >> 12)) != 0)
+            && self.frames.as_ref().is_none_or(|range| range.contains(&frame))
+    }
+}
+
+/// The registers an instruction can change, compared before and after it runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Registers {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub sp: u16,
+    pub delay: u8,
+}
+
+impl Registers {
+    fn changes(&self, after: &Registers) -> Vec<(String, u16, u16)> {
+        let mut changes = Vec::new();
+
+        for (register, (&old, &new)) in self.v.iter().zip(&after.v).enumerate() {
+            if old != new {
+                changes.push((format!("V{:X}", register), old as u16, new as u16));
+            }
+        }
+        if self.i != after.i {
+            changes.push(("I".to_string(), self.i, after.i));
+        }
+        if self.sp != after.sp {
+            changes.push(("SP".to_string(), self.sp, after.sp));
+        }
+        if self.delay != after.delay {
+            changes.push(("DT".to_string(), self.delay as u16, after.delay as u16));
+        }
+
+        changes
+    }
+}
+
+/// One executed instruction.
+pub struct Step<'a> {
+    pub frame: u64,
+    pub pc: u16,
+    pub opcode: u16,
+    pub mnemonic: &'a str,
+    pub next_pc: u16,
+    pub before: Registers,
+    pub after: Registers,
+}
+
+/// Writes executed instructions to a file, one per line. Write errors are
+/// kept until `finish` so that a full disk does not stop the game.
+pub struct Tracer {
+    out: BufWriter<Box<dyn Write + Send>>,
+    format: TraceFormat,
+    pub filter: TraceFilter,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    /// Traces to `path`, or to stdout when the path is `-`.
+    pub fn create(path: &str, format: TraceFormat, filter: TraceFilter) -> io::Result<Self> {
+        let out: Box<dyn Write + Send> = match path {
+            "-" => Box::new(io::stdout()),
+            _ => Box::new(File::create(path)?),
+        };
+
+        Ok(Tracer {
+            out: BufWriter::new(out),
+            format,
+            filter,
+            error: None,
+        })
+    }
+
+    pub fn record(&mut self, step: &Step) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let changes = step.before.changes(&step.after);
+        let result = match self.format {
+            TraceFormat::Text => {
+                let mut changes: Vec<String> = changes.iter()
+                    .map(|(name, old, new)| format!("{}={:x}->{:x}", name, old, new))
+                    .collect();
+                // only jumps, calls, returns and skips are worth pointing out
+                if step.next_pc != step.pc.wrapping_add(2) {
+                    changes.push(format!("PC->{:03x}", step.next_pc));
+                }
+                let line = format!("{:>6} {:03x} {:04x} {:-24} {}",
+                    step.frame, step.pc, step.opcode, step.mnemonic, changes.join(" "));
+                writeln!(self.out, "{}", line.trim_end())
+            },
+            TraceFormat::Json => {
+                let changes: Vec<String> = changes.iter()
+                    .map(|(name, old, new)| format!("\"{}\":[{},{}]", name, old, new))
+                    .collect();
+                writeln!(self.out, "{{\"frame\":{},\"pc\":{},\"opcode\":\"{:04x}\",\"mnemonic\":\"{}\",\"next_pc\":{},\"changes\":{{{}}}}}",
+                    step.frame, step.pc, step.opcode, escape(step.mnemonic), step.next_pc, changes.join(","))
+            },
+        };
+
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.out.flush(),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+}
@@ -0,0 +1,90 @@
+use crate::coverage::Access;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cause {
+    // FX33 or FX55 at `by` wrote the instruction in frame `frame`
+    Written { by: u16, frame: u64 },
+    // DXYN at `by` drew the instruction as a sprite
+    Drawn { by: u16 },
+}
+
+/// An instruction that was run from memory the program had used as data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    pub frame: u64,
+    pub pc: u16,
+    pub cause: Cause,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.cause {
+            Cause::Written {by, frame} => write!(f, "frame {}: running ${:03x}, which ${:03x} wrote in frame {}",
+                self.frame, self.pc, by, frame),
+            Cause::Drawn {by} => write!(f, "frame {}: running ${:03x}, which ${:03x} drew as a sprite",
+                self.frame, self.pc, by),
+        }
+    }
+}
+
+/// Looks out for self-modifying code and for jumps into sprite data. Each
+/// address is only reported the first time it runs.
+pub struct Watcher {
+    // stop the game when something turns up
+    pub break_on_event: bool,
+    written: Vec<Option<(u16, u64)>>,
+    drawn: Vec<Option<u16>>,
+    reported: Vec<bool>,
+    events: Vec<Event>,
+}
+
+impl Watcher {
+    pub fn new(break_on_event: bool) -> Self {
+        Watcher {
+            break_on_event,
+            written: vec![None; 4096],
+            drawn: vec![None; 4096],
+            reported: vec![false; 4096],
+            events: Vec::new(),
+        }
+    }
+
+    /// Notes a memory access by the instruction at `pc`. Returns true when a
+    /// fetch runs into memory written or drawn before.
+    pub fn mark(&mut self, access: Access, addresses: Range<usize>, pc: u16, frame: u64) -> bool {
+        let mut found = false;
+
+        for address in addresses {
+            let address = address & 0xfff;
+            match access {
+                Access::Write => self.written[address] = Some((pc, frame)),
+                Access::Sprite => self.drawn[address] = Some(pc),
+                Access::Load => {},
+                Access::Fetch => {
+                    // the second byte of the instruction counts as well
+                    for byte in [address, (address + 1) & 0xfff] {
+                        let cause = match (self.written[byte], self.drawn[byte]) {
+                            (Some((by, frame)), _) => Cause::Written {by, frame},
+                            (None, Some(by)) => Cause::Drawn {by},
+                            (None, None) => continue,
+                        };
+                        if !self.reported[address] {
+                            self.reported[address] = true;
+                            self.events.push(Event {frame, pc, cause});
+                            found = true;
+                        }
+                    }
+                },
+            }
+        }
+
+        found
+    }
+
+    /// Hands over what turned up since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+}
@@ -0,0 +1,158 @@
+//! The blocks backend has to be indistinguishable from the interpreter: the
+//! same machine after every frame, the same faults at the same place, however
+//! the frame's budget cuts a block off and whatever the program writes over.
+
+use chip8_emulator::blocks::Backend;
+use chip8_emulator::chip8::{Chip8State, Fault};
+use chip8_emulator::timing::Timing;
+use proptest::prelude::*;
+
+const FRAMES: u64 = 30;
+
+#[derive(Clone, Debug)]
+struct Setup {
+    program: Vec<u16>,
+    timing: Timing,
+    display_wait: bool,
+    seed: u64,
+    // keys held down in each frame
+    keys: Vec<u16>,
+}
+
+impl Setup {
+    fn build(&self, backend: Backend) -> Chip8State {
+        let rom: Vec<u8> = self.program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+        let mut chip8 = Chip8State::builder().rom(&rom).seed(self.seed).build();
+        chip8.timing = self.timing;
+        chip8.display_wait = self.display_wait;
+        chip8.backend = backend;
+        chip8
+    }
+}
+
+fn setup() -> impl Strategy<Value = Setup> {
+    // mostly jumps and calls back into the program, and registers stored over it
+    let opcode = prop_oneof![
+        (0x1000u16..=0x2fff).prop_map(|opcode| opcode & 0xf0ff | 0x0200),
+        (0x6000u16..=0x7fff),
+        (0u16..16).prop_map(|x| 0xf055 | x << 8),
+        (0u16..0x100).prop_map(|nnn| 0xa200 | nnn),
+        any::<u16>(),
+    ];
+    (
+        prop::collection::vec(opcode, 1..128),
+        prop_oneof![(1u32..50).prop_map(Timing::Ipf), Just(Timing::Vip)],
+        any::<bool>(),
+        any::<u64>(),
+        prop::collection::vec(any::<u16>(), FRAMES as usize),
+    ).prop_map(|(program, timing, display_wait, seed, keys)| Setup {program, timing, display_wait, seed, keys})
+}
+
+// the first thing that differs between the two machines
+fn difference(expected: &Chip8State, actual: &Chip8State) -> Option<String> {
+    let fields = [
+        ("pc", expected.pc == actual.pc),
+        ("V", expected.v == actual.v),
+        ("I", expected.i == actual.i),
+        ("the stack", expected.stack == actual.stack),
+        ("the delay timer", expected.delay == actual.delay),
+        ("the instruction count", expected.instructions == actual.instructions),
+        ("memory", expected.memory() == actual.memory()),
+        ("the screen", expected.screen.checksum() == actual.screen.checksum()),
+        ("the keys polled", expected.polled_keys == actual.polled_keys),
+    ];
+    fields.iter().find(|(_, same)| !same).map(|(name, _)| name.to_string())
+}
+
+// runs both backends frame by frame until one faults or the frames run out
+fn compare(setup: &Setup) -> Result<(), String> {
+    let mut interpreter = setup.build(Backend::Interpreter);
+    let mut blocks = setup.build(Backend::Blocks);
+
+    for frame in 0..FRAMES {
+        let keys = std::array::from_fn(|key| setup.keys[frame as usize] >> key & 1 == 1);
+        interpreter.set_keys(keys);
+        blocks.set_keys(keys);
+        let expected = interpreter.run_frame();
+        let actual = blocks.run_frame();
+
+        if expected != actual {
+            return Err(format!("frame {}: the interpreter gives {:?}, blocks {:?}", frame, expected, actual));
+        }
+        if let Some(different) = difference(&interpreter, &blocks) {
+            return Err(format!("frame {}: {} differs, pc ${:03x} and ${:03x}", frame, different, interpreter.pc, blocks.pc));
+        }
+        if expected.is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn blocks_match_the_interpreter(setup in setup()) {
+        if let Err(divergence) = compare(&setup) {
+            prop_assert!(false, "{}", divergence);
+        }
+    }
+}
+
+#[test]
+fn code_written_by_another_block_is_translated_again() {
+    let program = [
+        0x6073, // 200: V0 = $73
+        0x7101, // 202: V1 += 1
+        0xa20a, // 204: I = $20a
+        0xf155, // 206: write 73NN over $20a, NN being V1
+        0x1210, // 208: jump to $210
+        0x7300, // 20a: V3 += NN, a block of its own that the write has to drop
+        0x1202, // 20c: round again
+        0x0000, // 20e
+        0x120a, // 210: jump to $20a
+    ];
+    // one round a frame
+    let setup = Setup {program: program.to_vec(), timing: Timing::Ipf(7), display_wait: false, seed: 0, keys: vec![0; FRAMES as usize]};
+    assert_eq!(compare(&setup), Ok(()));
+
+    let mut chip8 = setup.build(Backend::Blocks);
+    for _ in 0..FRAMES {
+        chip8.run_frame().unwrap();
+    }
+    // 1 + 2 + ... + 30, less 256
+    assert_eq!(chip8.v[3], 209);
+}
+
+#[test]
+fn a_store_over_the_rest_of_its_own_loop() {
+    let program = [
+        0x6062, // 200: V0 = $62
+        0xa208, // 202: I = $208
+        0x7101, // 204: V1 += 1
+        0xf155, // 206: write 62NN over $208, NN being V1
+        0x6200, // 208: V2 = NN
+        0x1202, // 20a: round again
+    ];
+    let setup = Setup {program: program.to_vec(), timing: Timing::Ipf(40), display_wait: false, seed: 0, keys: vec![0; FRAMES as usize]};
+    assert_eq!(compare(&setup), Ok(()));
+
+    let mut chip8 = setup.build(Backend::Blocks);
+    chip8.run_frame().unwrap();
+    // the set, 7 rounds of 5 and 4 instructions into the eighth
+    assert_eq!(chip8.memory()[0x208..0x20a], [0x62, 8]);
+    assert_eq!(chip8.v[2], 8);
+}
+
+#[test]
+fn faults_in_the_middle_of_a_block() {
+    let program = [0x6001, 0x6102, 0xaffe, 0xf233, 0x6003];
+    let setup = Setup {program: program.to_vec(), timing: Timing::Vip, display_wait: false, seed: 0, keys: vec![0; FRAMES as usize]};
+    assert_eq!(compare(&setup), Ok(()));
+
+    let mut chip8 = setup.build(Backend::Blocks);
+    assert_eq!(chip8.run_frame(), Err(Fault::MemoryOutOfRange {pc: 0x206, address: 0x1000}));
+    assert_eq!(chip8.v[..2], [1, 2]);
+}
@@ -0,0 +1,98 @@
+//! Jobs run side by side have to end up exactly where the same job run on
+//! its own does, and come back in the order they went in.
+
+mod games;
+
+use chip8_emulator::batch::{self, Job};
+use chip8_emulator::blocks::Backend;
+use chip8_emulator::chip8::{Chip8State, Fault};
+use chip8_emulator::movie::Movie;
+use chip8_emulator::timing::Timing;
+
+// batch::run moves machines across threads
+const _: fn() = || {
+    fn send<T: Send>() {}
+    send::<Chip8State>();
+};
+
+fn games() -> Vec<Job> {
+    let recorded = [
+        ("snake", games::SNAKE, include_str!("games/snake.movie")),
+        ("pong", games::PONG, include_str!("games/pong.movie")),
+        ("breakout", games::BREAKOUT, include_str!("games/breakout.movie")),
+    ];
+
+    let mut jobs = Vec::new();
+    for (name, rom, movie) in recorded {
+        for (backend, timing) in [(Backend::Interpreter, Timing::Ipf(30)), (Backend::Blocks, Timing::Vip)] {
+            let mut job = Job::new(name, rom);
+            job.frames = 300;
+            job.timing = timing;
+            job.backend = backend;
+            job.movie = Some(Movie::parse(movie).unwrap());
+            jobs.push(job);
+        }
+    }
+    jobs
+}
+
+#[test]
+fn threads_give_the_same_outcomes_in_order() {
+    let jobs = games();
+    let alone: Vec<_> = jobs.iter().map(Job::run).collect();
+    let together = batch::run(&jobs, 4);
+
+    assert_eq!(together.len(), jobs.len());
+    for (expected, actual) in alone.iter().zip(&together) {
+        assert_eq!(actual.name, expected.name);
+        assert_eq!(actual.fault, None);
+        assert_eq!(actual.frames(), 300);
+        assert_eq!(actual.chip8.instructions, expected.chip8.instructions);
+        assert_eq!(actual.chip8.registers(), expected.chip8.registers());
+        assert_eq!(actual.chip8.memory(), expected.chip8.memory());
+        assert_eq!(actual.screen_hash(), expected.screen_hash());
+    }
+}
+
+#[test]
+fn seeds_are_kept_apart() {
+    // V0 = a random byte, then loop
+    let rom = [0xc0, 0xff, 0x12, 0x02];
+    let jobs: Vec<_> = (0..8).map(|seed| {
+        let mut job = Job::new(&format!("seed {}", seed), &rom);
+        job.seed = seed;
+        job.frames = 1;
+        job
+    }).collect();
+
+    let outcomes = batch::run(&jobs, 3);
+    for (seed, outcome) in outcomes.iter().enumerate() {
+        let mut expected = Chip8State::builder().rom(&rom).seed(seed as u64).build();
+        expected.run_frame().unwrap();
+        assert_eq!(outcome.chip8.v[0], expected.v[0], "seed {}", seed);
+    }
+}
+
+#[test]
+fn crashes_and_halts_stop_the_job() {
+    let jobs = [
+        Job::new("underflow", &[0x00, 0xee]),
+        Job::new("short", &[0x60, 0x01]),
+        Job::new("too large", &[0; 4096]),
+        Job::new("busy", &[0x12, 0x00]),
+    ];
+
+    let outcomes = batch::run(&jobs, 2);
+    assert_eq!(outcomes[0].fault, Some(Fault::StackUnderflow {pc: 0x200}));
+    assert_eq!(outcomes[0].frames(), 0);
+    assert!(outcomes[1].halted());
+    assert_eq!(outcomes[1].frames(), 1);
+    assert_eq!(outcomes[2].fault, Some(Fault::RomTooLarge {size: 4096}));
+    assert!(!outcomes[3].halted());
+    assert_eq!(outcomes[3].frames(), 600);
+}
+
+#[test]
+fn no_jobs() {
+    assert!(batch::run(&[], 4).is_empty());
+}
@@ -0,0 +1,343 @@
+//! Small self-written test programs run on the core without a window. Each
+//! one runs for a few frames and its registers, memory or screen are checked
+//! against known good values.
+
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::framebuffer::Framebuffer;
+use chip8_emulator::timing::Timing;
+
+/// Runs through the instructions and stores a result for each from 0x300 on.
+const OPCODES: &[u8] = &[
+    0xa3, 0x00,       // 200: I = 300, where the results go
+    0x60, 0x42,       // 202: V0 = 42
+    0x70, 0x01,       // 204: V0 += 1
+    0xf0, 0x55,       // 206: store 43
+    0x6f, 0x05,       // 208: VF = 5
+    0x60, 0xff,       // 20a: V0 = ff
+    0x70, 0x02,       // 20c: V0 += 2, wrapping
+    0xf0, 0x55,       // 20e: store 01
+    0x80, 0xf0,       // 210: V0 = VF, 7XNN leaves it alone
+    0xf0, 0x55,       // 212: store 05
+    0x61, 0x07,       // 214: V1 = 07
+    0x80, 0x10,       // 216: V0 = V1
+    0xf0, 0x55,       // 218: store 07
+    0x60, 0xf0,       // 21a: V0 = f0
+    0x61, 0x0f,       // 21c: V1 = 0f
+    0x80, 0x11,       // 21e: V0 |= V1
+    0xf0, 0x55,       // 220: store ff
+    0x60, 0xf0,       // 222: V0 = f0
+    0x61, 0x3c,       // 224: V1 = 3c
+    0x80, 0x12,       // 226: V0 &= V1
+    0xf0, 0x55,       // 228: store 30
+    0x60, 0xf0,       // 22a: V0 = f0
+    0x61, 0x3c,       // 22c: V1 = 3c
+    0x80, 0x13,       // 22e: V0 ^= V1
+    0xf0, 0x55,       // 230: store cc
+    0x60, 0x01,       // 232: V0 = 01
+    0x30, 0x01,       // 234: skip if V0 == 01
+    0x60, 0x02,       // 236: V0 = 02, skipped
+    0xf0, 0x55,       // 238: store 01
+    0x60, 0x01,       // 23a: V0 = 01
+    0x30, 0x02,       // 23c: skip if V0 == 02
+    0x60, 0x02,       // 23e: V0 = 02
+    0xf0, 0x55,       // 240: store 02
+    0x60, 0x01,       // 242: V0 = 01
+    0x40, 0x01,       // 244: skip if V0 != 01
+    0x60, 0x02,       // 246: V0 = 02
+    0xf0, 0x55,       // 248: store 02
+    0x60, 0x01,       // 24a: V0 = 01
+    0x40, 0x02,       // 24c: skip if V0 != 02
+    0x60, 0x02,       // 24e: V0 = 02, skipped
+    0xf0, 0x55,       // 250: store 01
+    0x60, 0x01,       // 252: V0 = 01
+    0x61, 0x01,       // 254: V1 = 01
+    0x50, 0x10,       // 256: skip if V0 == V1
+    0x60, 0x02,       // 258: V0 = 02, skipped
+    0xf0, 0x55,       // 25a: store 01
+    0x60, 0x01,       // 25c: V0 = 01
+    0x61, 0x03,       // 25e: V1 = 03
+    0x90, 0x10,       // 260: skip if V0 != V1
+    0x60, 0x02,       // 262: V0 = 02, skipped
+    0xf0, 0x55,       // 264: store 01
+    0x60, 0x01,       // 266: V0 = 01
+    0x61, 0x01,       // 268: V1 = 01
+    0x90, 0x10,       // 26a: skip if V0 != V1
+    0x60, 0x02,       // 26c: V0 = 02
+    0xf0, 0x55,       // 26e: store 02
+    0x22, 0xa2,       // 270: call sub
+    0xf0, 0x55,       // 272: store 99
+    0x60, 0x02,       // 274: V0 = 02
+    0xb2, 0x78,       // 276: jump to beforeland + V0
+    0x60, 0x77,       // 278: V0 = 77, skipped
+    0xf0, 0x55,       // 27a: store 02
+    0x60, 0x33,       // 27c: V0 = 33
+    0xf0, 0x15,       // 27e: delay = V0
+    0x60, 0x00,       // 280: V0 = 00
+    0xf0, 0x07,       // 282: V0 = delay
+    0xf0, 0x55,       // 284: store 33
+    0x60, 0x9c,       // 286: V0 = 156
+    0xf0, 0x33,       // 288: store 01 05 06
+    0x60, 0x03,       // 28a: V0 = 03
+    0xf0, 0x1e,       // 28c: I += V0
+    0xa2, 0xa6,       // 28e: I = data
+    0xf2, 0x65,       // 290: V0-V2 = de ad be
+    0xa3, 0x14,       // 292: I = 314
+    0xf2, 0x55,       // 294: store de ad be
+    0x60, 0x0a,       // 296: V0 = 0a
+    0xf0, 0x29,       // 298: I = the A font sprite
+    0xf0, 0x65,       // 29a: V0 = its first row
+    0xa3, 0x17,       // 29c: I = 317
+    0xf0, 0x55,       // 29e: store f0
+    0x12, 0xa0,       // 2a0: loop forever
+    0x60, 0x99,       // 2a2: V0 = 99
+    0x00, 0xee,       // 2a4: return
+    0xde, 0xad, 0xbe, // 2a6: data
+];
+
+/// Stores the result and VF of each arithmetic instruction from 0x300 on.
+const FLAGS: &[u8] = &[
+    0xa3, 0x00, // 200: I = 300
+    0x60, 0x10, // 202: V0 = 10
+    0x61, 0x20, // 204: V1 = 20
+    0x80, 0x14, // 206: V0 += V1
+    0x81, 0xf0, // 208: V1 = VF
+    0xf1, 0x55, // 20a: store 30 00
+    0x60, 0xf0, // 20c: V0 = f0
+    0x61, 0x20, // 20e: V1 = 20
+    0x80, 0x14, // 210: V0 += V1
+    0x81, 0xf0, // 212: V1 = VF
+    0xf1, 0x55, // 214: store 10 01
+    0x60, 0x30, // 216: V0 = 30
+    0x61, 0x10, // 218: V1 = 10
+    0x80, 0x15, // 21a: V0 -= V1
+    0x81, 0xf0, // 21c: V1 = VF
+    0xf1, 0x55, // 21e: store 20 01
+    0x60, 0x10, // 220: V0 = 10
+    0x61, 0x30, // 222: V1 = 30
+    0x80, 0x15, // 224: V0 -= V1
+    0x81, 0xf0, // 226: V1 = VF
+    0xf1, 0x55, // 228: store e0 00
+    0x61, 0x05, // 22a: V1 = 05
+    0x80, 0x16, // 22c: V0 = V1 >> 1
+    0x81, 0xf0, // 22e: V1 = VF
+    0xf1, 0x55, // 230: store 02 01
+    0x61, 0x04, // 232: V1 = 04
+    0x80, 0x16, // 234: V0 = V1 >> 1
+    0x81, 0xf0, // 236: V1 = VF
+    0xf1, 0x55, // 238: store 02 00
+    0x60, 0x10, // 23a: V0 = 10
+    0x61, 0x30, // 23c: V1 = 30
+    0x80, 0x17, // 23e: V0 = V1 - V0
+    0x81, 0xf0, // 240: V1 = VF
+    0xf1, 0x55, // 242: store 20 01
+    0x60, 0x30, // 244: V0 = 30
+    0x61, 0x10, // 246: V1 = 10
+    0x80, 0x17, // 248: V0 = V1 - V0
+    0x81, 0xf0, // 24a: V1 = VF
+    0xf1, 0x55, // 24c: store e0 00
+    0x61, 0x81, // 24e: V1 = 81
+    0x80, 0x1e, // 250: V0 = V1 << 1
+    0x81, 0xf0, // 252: V1 = VF
+    0xf1, 0x55, // 254: store 02 01
+    0x61, 0x41, // 256: V1 = 41
+    0x80, 0x1e, // 258: V0 = V1 << 1
+    0x81, 0xf0, // 25a: V1 = VF
+    0xf1, 0x55, // 25c: store 82 00
+    0x6f, 0x10, // 25e: VF = 10
+    0x61, 0x20, // 260: V1 = 20
+    0x8f, 0x14, // 262: VF += V1, the flag wins
+    0x80, 0xf0, // 264: V0 = VF
+    0xf0, 0x55, // 266: store 00
+    0x6f, 0xff, // 268: VF = ff
+    0x61, 0x01, // 26a: V1 = 01
+    0x8f, 0x14, // 26c: VF += V1, the flag wins
+    0x80, 0xf0, // 26e: V0 = VF
+    0xf0, 0x55, // 270: store 01
+    0x12, 0x72, // 272: loop forever
+];
+
+/// Checks the original chip-8 behaviour where interpreters disagree. Results
+/// go from 0x300 on and the sprites are checked on screen.
+const QUIRKS: &[u8] = &[
+    0xa3, 0x00, // 200: I = 300
+    0x6f, 0x05, // 202: VF = 05
+    0x60, 0x01, // 204: V0 = 01
+    0x61, 0x02, // 206: V1 = 02
+    0x80, 0x11, // 208: V0 |= V1, VF is reset
+    0x80, 0xf0, // 20a: V0 = VF
+    0xf0, 0x55, // 20c: store 00
+    0x6f, 0x05, // 20e: VF = 05
+    0x80, 0x12, // 210: V0 &= V1, VF is reset
+    0x80, 0xf0, // 212: V0 = VF
+    0xf0, 0x55, // 214: store 00
+    0x6f, 0x05, // 216: VF = 05
+    0x80, 0x13, // 218: V0 ^= V1, VF is reset
+    0x80, 0xf0, // 21a: V0 = VF
+    0xf0, 0x55, // 21c: store 00
+    0x60, 0x01, // 21e: V0 = 01
+    0x61, 0x04, // 220: V1 = 04
+    0x80, 0x16, // 222: V0 = V1 >> 1, not V0 >> 1
+    0xf0, 0x55, // 224: store 02
+    0x60, 0x01, // 226: V0 = 01
+    0x61, 0x04, // 228: V1 = 04
+    0x80, 0x1e, // 22a: V0 = V1 << 1, not V0 << 1
+    0xf0, 0x55, // 22c: store 08
+    0x62, 0x00, // 22e: V2 = 00
+    0x60, 0x04, // 230: V0 = 04
+    0xb2, 0x34, // 232: jump to skipped + V0, not + V2
+    0x60, 0x77, // 234: V0 = 77
+    0x60, 0x77, // 236: V0 = 77
+    0xf0, 0x55, // 238: store 04
+    0xa2, 0x50, // 23a: I = a row of 8 pixels
+    0x60, 0x3c, // 23c: V0 = 60
+    0x61, 0x00, // 23e: V1 = 00
+    0xd0, 0x11, // 240: draw at 60,0, the right half is cut off
+    0x60, 0x42, // 242: V0 = 66
+    0x61, 0x05, // 244: V1 = 05
+    0xd0, 0x11, // 246: draw at 2,5, coordinates wrap
+    0x60, 0x00, // 248: V0 = 00
+    0x61, 0x1f, // 24a: V1 = 31
+    0xd0, 0x12, // 24c: draw two rows at 0,31, the second is cut off
+    0x12, 0x4e, // 24e: loop forever
+    0xff, 0xff, // 250: row
+];
+
+/// Draws all 16 font digits in two rows, then tests collisions.
+const DISPLAY: &[u8] = &[
+    0x00, 0xe0, // 200: clear the screen
+    0x60, 0x00, // 202: V0 = digit 0
+    0x61, 0x01, // 204: V1 = x
+    0x62, 0x01, // 206: V2 = y
+    0xf0, 0x29, // 208: I = the digit's sprite
+    0xd1, 0x25, // 20a: draw it
+    0x71, 0x05, // 20c: x += 5
+    0x70, 0x01, // 20e: next digit
+    0x40, 0x08, // 210: skip unless 8 digits are done
+    0x22, 0x1a, // 212: start the second row
+    0x40, 0x10, // 214: skip unless all 16 are done
+    0x12, 0x20, // 216: done
+    0x12, 0x08, // 218: next
+    0x61, 0x01, // 21a: x = 1
+    0x62, 0x07, // 21c: y = 7
+    0x00, 0xee, // 21e: return
+    0xa2, 0x32, // 220: I = a row of 8 pixels
+    0x6c, 0x30, // 222: VC = 48
+    0x6d, 0x10, // 224: VD = 16
+    0xdc, 0xd1, // 226: draw it
+    0xdc, 0xd1, // 228: draw it again, erasing it
+    0x8e, 0xf0, // 22a: VE = VF, the collision
+    0xdc, 0xd1, // 22c: draw it once more
+    0x8b, 0xf0, // 22e: VB = VF, no collision
+    0x12, 0x30, // 230: loop forever
+    0xff,       // 232: square
+];
+
+/// Waits for a key and then checks that it is held down.
+const KEYS: &[u8] = &[
+    0xf3, 0x0a, // 200: V3 = the next key pressed
+    0xe3, 0x9e, // 202: skip if the key is held
+    0x64, 0x01, // 204: V4 = 01
+    0xe3, 0xa1, // 206: skip if the key is not held
+    0x65, 0x02, // 208: V5 = 02
+    0x12, 0x0a, // 20a: loop forever
+];
+
+/// Draws a pixel outside the 64x32 screen in SCHIP's 128x64 mode.
+const HIRES: &[u8] = &[
+    0x00, 0xff, // 200: high resolution
+    0x60, 0x64, // 202: V0 = 100
+    0x61, 0x28, // 204: V1 = 40
+    0xa2, 0x0c, // 206: I = a single pixel
+    0xd0, 0x11, // 208: draw it
+    0x12, 0x0a, // 20a: loop forever
+    0x80,       // 20c: dot
+];
+
+
+/// Loads the program and runs it for `frames` frames, fast enough that the
+/// programs above finish in the first one.
+fn run(rom: &[u8], frames: u64) -> Chip8State {
+    let mut chip8 = Chip8State::new([0; 4096], Framebuffer::new(), [0; 16], 0);
+    chip8.load_rom(rom).unwrap();
+    chip8.timing = Timing::Ipf(1000);
+
+    for _ in 0..frames {
+        chip8.run_frame().unwrap();
+    }
+
+    chip8
+}
+
+#[test]
+fn opcodes() {
+    let chip8 = run(OPCODES, 2);
+
+    assert_eq!(chip8.memory()[0x300..0x318], [
+        0x43, 0x01, 0x05, 0x07, 0xff, 0x30, 0xcc, 0x01,
+        0x02, 0x02, 0x01, 0x01, 0x01, 0x02, 0x99, 0x02,
+        0x33, 0x01, 0x05, 0x06, 0xde, 0xad, 0xbe, 0xf0,
+    ]);
+    assert_eq!(chip8.i, 0x318);
+    assert_eq!(chip8.sp, 0);
+    assert!(chip8.stack.is_empty());
+}
+
+#[test]
+fn flags() {
+    let chip8 = run(FLAGS, 2);
+
+    assert_eq!(chip8.memory()[0x300..0x316], [
+        0x30, 0x00, 0x10, 0x01, 0x20, 0x01, 0xe0, 0x00,
+        0x02, 0x01, 0x02, 0x00, 0x20, 0x01, 0xe0, 0x00,
+        0x02, 0x01, 0x82, 0x00, 0x00, 0x01,
+    ]);
+}
+
+#[test]
+fn quirks() {
+    let chip8 = run(QUIRKS, 2);
+
+    assert_eq!(chip8.memory()[0x300..0x306], [0x00, 0x00, 0x00, 0x02, 0x08, 0x04]);
+
+    let lit = |x, y| chip8.screen.pixel(x, y) == 1;
+    assert!((60..64).all(|x| lit(x, 0)));
+    assert!(!(0..4).any(|x| lit(x, 0)));
+    assert!((2..10).all(|x| lit(x, 5)));
+    assert!((0..8).all(|x| lit(x, 31)));
+    assert_eq!(chip8.screen.pixels().filter(|&pixel| pixel != 0).count(), 4 + 8 + 8);
+}
+
+#[test]
+fn display() {
+    let chip8 = run(DISPLAY, 2);
+
+    assert_eq!(chip8.v[0xe], 1);
+    assert_eq!(chip8.v[0xb], 0);
+    // 0-7 and 8-F in two rows, plus the square
+    assert_eq!(chip8.screen.checksum(), 0x36648bdc5ac0a39a);
+}
+
+#[test]
+fn keys() {
+    let mut chip8 = run(KEYS, 3);
+    // still waiting at FX0A
+    assert_eq!(chip8.pc, 0x200);
+
+    let mut keys = [false; 16];
+    keys[5] = true;
+    chip8.set_keys(keys);
+    chip8.run_frame().unwrap();
+
+    assert_eq!(chip8.v[3], 5);
+    assert_eq!(chip8.v[4], 0);
+    assert_eq!(chip8.v[5], 2);
+}
+
+#[test]
+fn hires() {
+    let chip8 = run(HIRES, 1);
+
+    assert_eq!(chip8.screen.width(), 128);
+    assert_eq!(chip8.screen.pixel(100, 40), 1);
+    assert_eq!(chip8.screen.pixels().filter(|&pixel| pixel != 0).count(), 1);
+}
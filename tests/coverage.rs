@@ -0,0 +1,43 @@
+//! The coverage listing of a small program that draws, stores digits over
+//! its own bytes and never gets to its last instruction.
+
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::coverage::Coverage;
+
+const ROM: [u8; 20] = [
+    0xa2, 0x0c, // I = sprite
+    0xd0, 0x11, // draw one row of it
+    0x62, 0x7b, // V2 = 123
+    0xa2, 0x0e, // I = digits
+    0xf2, 0x33, // store the digits of V2
+    0x12, 0x0a, // loop here
+    0xf0, 0x00, // sprite
+    0x00, 0x00, 0x00, 0x00, // digits
+    0x00, 0xe0, // never reached
+];
+
+#[test]
+fn listing_shows_code_data_and_unreached_bytes() {
+    let mut chip8 = Chip8State::builder().rom(&ROM).build();
+    chip8.coverage = Some(Coverage::default());
+    chip8.run_frame().unwrap();
+
+    let mut report = Vec::new();
+    chip8.coverage.as_ref().unwrap().report(&mut report, chip8.memory(), 0x200, chip8.rom_end as usize).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    let lines: Vec<&str> = report.lines().collect();
+
+    assert_eq!(lines[0], "12 of 20 bytes executed (60.0%), 1 read as data, 3 written");
+    assert_eq!(lines[3..], [
+        "         1    200: a20c  MVI        I,#$20c",
+        "         1    202: d011  SPRITE     V0, V1, #$1",
+        "         1    204: 627b  MVI        V2,#$7b",
+        "         1    206: a20e  MVI        I,#$20e",
+        "         1    208: f233  MOVBCD     (I),V2",
+        "         5    20a: 120a  JUMP       $20a",
+        "         - r  20c: f000  ████.... ........",
+        "         -  w 20e: 0102",
+        "         -  w 210: 0300",
+        "     #####    212: 00e0",
+    ]);
+}
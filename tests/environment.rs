@@ -0,0 +1,107 @@
+//! The gym-style environment: rewards and the end of an episode read out of
+//! memory, frameskip, and seeded resets that play out the same every time.
+
+use chip8_emulator::chip8::Fault;
+use chip8_emulator::environment::{Environment, Extractor};
+
+// ten points for every press of any key, kept as a BCD score at $300
+const SCORE: [u8; 10] = [
+    0xa3, 0x00, // 200: I = $300
+    0xf1, 0x0a, // 202: wait for a key
+    0x70, 0x0a, // 204: V0 += 10
+    0xf0, 0x33, // 206: store V0 as BCD at $300
+    0x12, 0x02, // 208: round again
+];
+
+const NONE: [bool; 16] = [false; 16];
+const KEY_5: [bool; 16] = {
+    let mut keys = [false; 16];
+    keys[5] = true;
+    keys
+};
+
+fn score() -> Environment {
+    let mut environment = Environment::new(&SCORE).unwrap();
+    environment.reward = Some(Extractor::Bcd {address: 0x300, digits: 3});
+    // 30 points
+    environment.done = vec![(Extractor::Byte(0x301), 3)];
+    environment.reset(0);
+    environment
+}
+
+#[test]
+fn rewards_come_from_the_score() {
+    let mut environment = score();
+
+    assert_eq!(environment.step(KEY_5).1, 10.0);
+    // held down, not pressed again
+    assert_eq!(environment.step(KEY_5).1, 0.0);
+    assert_eq!(environment.step(NONE).1, 0.0);
+    let (_, reward, done) = environment.step(KEY_5);
+    assert_eq!((reward, done), (10.0, false));
+
+    environment.step(NONE);
+    let (_, reward, done) = environment.step(KEY_5);
+    assert_eq!((reward, done), (10.0, true));
+    assert_eq!(environment.chip8().memory()[0x300..0x303], [0, 3, 0]);
+
+    // nothing happens once it is done
+    let frame = environment.chip8().frame;
+    assert_eq!(environment.step(NONE).1, 0.0);
+    assert_eq!(environment.chip8().frame, frame);
+}
+
+#[test]
+fn frameskip_holds_the_keys_and_adds_up_the_reward() {
+    let mut environment = score();
+    environment.frameskip = 3;
+
+    let (_, reward, done) = environment.step(KEY_5);
+    assert_eq!((reward, done), (10.0, false));
+    assert_eq!(environment.chip8().frame, 3);
+}
+
+#[test]
+fn reset_starts_over() {
+    let mut environment = score();
+    environment.step(KEY_5);
+    environment.step(NONE);
+    environment.step(KEY_5);
+
+    environment.reset(0);
+    assert_eq!(environment.chip8().frame, 0);
+    assert_eq!(environment.chip8().memory()[0x300..0x303], [0, 0, 0]);
+    assert_eq!(environment.step(KEY_5).1, 10.0);
+}
+
+#[test]
+fn the_seed_decides_the_episode() {
+    // a random byte drawn at a random height, then nothing
+    let rom = [0xc0, 0xff, 0xc1, 0x1f, 0xa2, 0x00, 0xd0, 0x15, 0x12, 0x08];
+    let mut environment = Environment::new(&rom).unwrap();
+    let mut play = |seed| {
+        environment.reset(seed);
+        environment.step(NONE).0.checksum()
+    };
+
+    assert_eq!(play(1), play(1));
+    assert_ne!(play(1), play(2));
+}
+
+#[test]
+fn faults_halts_and_frame_limits_end_the_episode() {
+    let mut environment = Environment::new(&[0x00, 0xee]).unwrap();
+    assert!(environment.step(NONE).2);
+    assert_eq!(environment.fault(), Some(Fault::StackUnderflow {pc: 0x200}));
+
+    let mut environment = Environment::new(&[0x60, 0x01]).unwrap();
+    assert!(environment.step(NONE).2);
+    assert_eq!(environment.fault(), None);
+
+    let mut environment = Environment::new(&[0x12, 0x00]).unwrap();
+    environment.max_frames = Some(2);
+    assert!(!environment.step(NONE).2);
+    assert!(environment.step(NONE).2);
+
+    assert!(matches!(Environment::new(&[0; 4096]), Err(Fault::RomTooLarge {size: 4096})));
+}
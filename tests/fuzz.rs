@@ -0,0 +1,140 @@
+//! The deterministic side of `fuzz/`: arbitrary programs and machine states
+//! must never panic the core, and anything that goes wrong has to come back
+//! as a `Fault` with the machine left at the instruction that caused it.
+
+use chip8_emulator::chip8::{Chip8State, Fault, STACK_SIZE};
+use chip8_emulator::coverage::Coverage;
+use chip8_emulator::profile::Profiler;
+use chip8_emulator::timing::Timing;
+use chip8_emulator::watch::Watcher;
+use proptest::prelude::*;
+
+// the pc a fault was raised at, if it came from an instruction
+fn fault_pc(fault: Fault) -> Option<u16> {
+    match fault {
+        Fault::StackUnderflow {pc} | Fault::StackOverflow {pc} | Fault::InvalidKey {pc, ..} => Some(pc),
+        Fault::MemoryOutOfRange {pc, ..} => Some(pc),
+        Fault::RomTooLarge {..} => None,
+    }
+}
+
+proptest! {
+    #[test]
+    fn any_program_runs_or_faults(rom in prop::collection::vec(any::<u8>(), 0..4096), keys in any::<u16>(), vip in any::<bool>()) {
+        let mut chip8 = Chip8State::builder().build();
+        match chip8.load_rom(&rom) {
+            Ok(()) => prop_assert!(rom.len() <= 4096 - 0x200),
+            Err(fault) => {
+                prop_assert_eq!(fault, Fault::RomTooLarge {size: rom.len()});
+                return Ok(());
+            },
+        }
+        chip8.timing = if vip {Timing::Vip} else {Timing::Ipf(200)};
+        chip8.profiler = Some(Profiler::new(200, "instructions"));
+        chip8.coverage = Some(Coverage::default());
+        chip8.watcher = Some(Watcher::new(false));
+
+        for frame in 0..30 {
+            chip8.set_keys(std::array::from_fn(|key| keys >> key & 1 == 1 && frame % 2 == 0));
+            if let Err(fault) = chip8.run_frame() {
+                prop_assert_eq!(fault_pc(fault), Some(chip8.pc));
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn any_instruction_in_any_state_runs_or_faults(
+        opcode in any::<u16>(),
+        v in any::<[u8; 16]>(),
+        i in any::<u16>(),
+        pc in 0u16..0x1000,
+        stack in prop::collection::vec(0u16..0x1000, 0..=STACK_SIZE),
+    ) {
+        let mut builder = Chip8State::builder().i(i).pc(pc).stack(&stack);
+        for (register, &value) in v.iter().enumerate() {
+            builder = builder.v(register, value);
+        }
+        let mut chip8 = builder.build();
+
+        if let Err(fault) = chip8.execute(opcode) {
+            prop_assert_eq!(fault_pc(fault), Some(pc));
+            prop_assert_eq!(chip8.pc, pc);
+            prop_assert_eq!(chip8.stack.len(), stack.len());
+        }
+    }
+}
+
+#[test]
+fn return_without_call_underflows() {
+    let mut chip8 = Chip8State::builder().build();
+
+    assert_eq!(chip8.execute(0x00ee), Err(Fault::StackUnderflow {pc: 0x200}));
+    assert_eq!(chip8.pc, 0x200);
+}
+
+#[test]
+fn endless_recursion_overflows() {
+    let mut chip8 = Chip8State::builder().rom(&[0x22, 0x00]).build();
+    chip8.timing = Timing::Ipf(100);
+
+    assert_eq!(chip8.run_frame(), Err(Fault::StackOverflow {pc: 0x200}));
+    assert_eq!(chip8.stack.len(), STACK_SIZE);
+    // the call that overflowed is not counted as run
+    assert_eq!(chip8.instructions, STACK_SIZE as u64);
+}
+
+#[test]
+fn fetch_past_the_end_of_memory() {
+    let mut chip8 = Chip8State::builder().pc(0xfff).build();
+
+    assert_eq!(chip8.step(), Err(Fault::MemoryOutOfRange {pc: 0xfff, address: 0x1000}));
+}
+
+#[test]
+fn sprite_past_the_end_of_memory() {
+    let mut chip8 = Chip8State::builder().i(0xffc).v(0xf, 7).build();
+
+    assert_eq!(chip8.execute(0xd005), Err(Fault::MemoryOutOfRange {pc: 0x200, address: 0x1000}));
+    // nothing is drawn and VF is left alone
+    assert_eq!(chip8.screen.pixels().filter(|&pixel| pixel != 0).count(), 0);
+    assert_eq!(chip8.v[0xf], 7);
+}
+
+#[test]
+fn bcd_past_the_end_of_memory() {
+    let mut chip8 = Chip8State::builder().i(0xffe).build();
+
+    assert_eq!(chip8.execute(0xf033), Err(Fault::MemoryOutOfRange {pc: 0x200, address: 0x1000}));
+    assert_eq!(chip8.memory()[0xffe], 0);
+}
+
+#[test]
+fn store_and_load_past_the_end_of_memory() {
+    let mut chip8 = Chip8State::builder().i(0xff8).build();
+
+    assert_eq!(chip8.execute(0xff55), Err(Fault::MemoryOutOfRange {pc: 0x200, address: 0x1007}));
+    assert_eq!(chip8.execute(0xff65), Err(Fault::MemoryOutOfRange {pc: 0x200, address: 0x1007}));
+    assert_eq!(chip8.i, 0xff8);
+
+    // a large I wraps instead of overflowing
+    let mut chip8 = Chip8State::builder().i(0xffff).v(0, 1).build();
+    chip8.execute(0xf01e).unwrap();
+    assert_eq!(chip8.i, 0);
+}
+
+#[test]
+fn skip_on_a_key_that_does_not_exist() {
+    let mut chip8 = Chip8State::builder().v(3, 0x10).build();
+
+    assert_eq!(chip8.execute(0xe39e), Err(Fault::InvalidKey {pc: 0x200, key: 0x10}));
+    assert_eq!(chip8.execute(0xe3a1), Err(Fault::InvalidKey {pc: 0x200, key: 0x10}));
+}
+
+#[test]
+fn rom_too_large() {
+    let mut chip8 = Chip8State::builder().build();
+
+    assert_eq!(chip8.load_rom(&[0; 3585]), Err(Fault::RomTooLarge {size: 3585}));
+    assert!(chip8.load_rom(&[0; 3584]).is_ok());
+}
@@ -0,0 +1,353 @@
+//! Small clean-room versions of the games shown in the README, written for
+//! the golden tests. Every one runs off the delay timer and uses CXNN, so
+//! they need a seed and a movie to play out the same way each time.
+
+/// Snake: 2, 4, 6 and 8 steer, eating the dot grows the snake by three and
+/// scores a point, running into itself ends the game and shows the score.
+/// The body is a ring of x, y pairs at 400.
+pub const SNAKE: &[u8] = &[
+    0x62, 0x09, // 200: V2 = head x
+    0x63, 0x0a, // 202: V3 = head y
+    0x64, 0x01, // 204: V4 = dx, heading right
+    0x65, 0x00, // 206: V5 = dy
+    0x68, 0x04, // 208: V8 = segments still to grow
+    0x6a, 0x00, // 20a: VA = where the next head goes in the ring at 400
+    0x6b, 0x00, // 20c: VB = where the tail is
+    0x6c, 0x00, // 20e: VC = score
+    0x22, 0x80, // 210: place the first food
+    0x60, 0x04, // 212: V0 = 4
+    0xf0, 0x15, // 214: delay = V0
+    0xf0, 0x07, // 216: V0 = delay
+    0x30, 0x00, // 218: skip if it ran out
+    0x12, 0x16, // 21a: keep waiting
+    0x60, 0x02, // 21c: key 2 is up
+    0xe0, 0xa1, // 21e: skip unless held
+    0x22, 0xb0, // 220: turn up
+    0x60, 0x08, // 222: key 8 is down
+    0xe0, 0xa1, // 224: skip unless held
+    0x22, 0xba, // 226: turn down
+    0x60, 0x04, // 228: key 4 is left
+    0xe0, 0xa1, // 22a: skip unless held
+    0x22, 0xc4, // 22c: turn left
+    0x60, 0x06, // 22e: key 6 is right
+    0xe0, 0xa1, // 230: skip unless held
+    0x22, 0xce, // 232: turn right
+    0x82, 0x44, // 234: x += dx
+    0x83, 0x54, // 236: y += dy
+    0x60, 0x3f, // 238: V0 = 3f
+    0x82, 0x02, // 23a: wrap x
+    0x60, 0x1f, // 23c: V0 = 1f
+    0x83, 0x02, // 23e: wrap y
+    0xa2, 0xd8, // 240: I = dot
+    0xd2, 0x31, // 242: draw the head
+    0x3f, 0x00, // 244: skip if it hit nothing
+    0x22, 0x72, // 246: food or the snake itself
+    0xa4, 0x00, // 248: I = ring
+    0xfa, 0x1e, // 24a: I += head index
+    0x80, 0x20, // 24c: V0 = x
+    0x81, 0x30, // 24e: V1 = y
+    0xf1, 0x55, // 250: store the head
+    0x7a, 0x02, // 252: head index += 2
+    0x60, 0x7f, // 254: V0 = 7f
+    0x8a, 0x02, // 256: wrap it
+    0x38, 0x00, // 258: skip if done growing
+    0x12, 0x6e, // 25a: grow instead of moving the tail
+    0xa4, 0x00, // 25c: I = ring
+    0xfb, 0x1e, // 25e: I += tail index
+    0xf1, 0x65, // 260: V0, V1 = tail
+    0xa2, 0xd8, // 262: I = dot
+    0xd0, 0x11, // 264: erase the tail
+    0x7b, 0x02, // 266: tail index += 2
+    0x60, 0x7f, // 268: V0 = 7f
+    0x8b, 0x02, // 26a: wrap it
+    0x12, 0x12, // 26c: next move
+    0x78, 0xff, // 26e: one segment less to grow
+    0x12, 0x12, // 270: next move
+    0x52, 0x60, // 272: skip if the head is on the food x
+    0x12, 0x92, // 274: ran into itself
+    0x53, 0x70, // 276: skip if on the food y
+    0x12, 0x92, // 278: ran into itself
+    0xd2, 0x31, // 27a: draw the head again, eating turned it off
+    0x78, 0x03, // 27c: grow 3
+    0x7c, 0x01, // 27e: score += 1
+    0xc6, 0x3f, // 280: V6 = food x, at random
+    0xc7, 0x1f, // 282: V7 = food y
+    0xa2, 0xd8, // 284: I = dot
+    0xd6, 0x71, // 286: draw the food
+    0x3f, 0x00, // 288: skip if it landed on a free spot
+    0x12, 0x8e, // 28a: try again
+    0x00, 0xee, // 28c: return
+    0xd6, 0x71, // 28e: take it back off the snake
+    0x12, 0x80, // 290: pick another spot
+    0x00, 0xe0, // 292: clear the screen
+    0xa3, 0x00, // 294: I = 300
+    0xfc, 0x33, // 296: score as decimal
+    0xf2, 0x65, // 298: V0-V2 = digits
+    0x6d, 0x18, // 29a: VD = x
+    0x6e, 0x0d, // 29c: VE = y
+    0xf0, 0x29, // 29e: I = hundreds
+    0xdd, 0xe5, // 2a0: draw
+    0x7d, 0x05, // 2a2: x += 5
+    0xf1, 0x29, // 2a4: I = tens
+    0xdd, 0xe5, // 2a6: draw
+    0x7d, 0x05, // 2a8: x += 5
+    0xf2, 0x29, // 2aa: I = ones
+    0xdd, 0xe5, // 2ac: draw
+    0x12, 0xae, // 2ae: done
+    0x45, 0x01, // 2b0: skip unless going down
+    0x00, 0xee, // 2b2: no turning back
+    0x64, 0x00, // 2b4: dx = 0
+    0x65, 0xff, // 2b6: dy = -1
+    0x00, 0xee, // 2b8: return
+    0x45, 0xff, // 2ba: skip unless going up
+    0x00, 0xee, // 2bc: no turning back
+    0x64, 0x00, // 2be: dx = 0
+    0x65, 0x01, // 2c0: dy = 1
+    0x00, 0xee, // 2c2: return
+    0x44, 0x01, // 2c4: skip unless going right
+    0x00, 0xee, // 2c6: no turning back
+    0x64, 0xff, // 2c8: dx = -1
+    0x65, 0x00, // 2ca: dy = 0
+    0x00, 0xee, // 2cc: return
+    0x44, 0xff, // 2ce: skip unless going left
+    0x00, 0xee, // 2d0: no turning back
+    0x64, 0x01, // 2d2: dx = 1
+    0x65, 0x00, // 2d4: dy = 0
+    0x00, 0xee, // 2d6: return
+    0x80,       // 2d8: dot
+];
+
+/// Pong: 1 and 4 move the left paddle, the computer plays the right one
+/// every other move. A point clears the screen to show the score for a second.
+pub const PONG: &[u8] = &[
+    0x68, 0x00,                         // 200: V8 = left score
+    0x69, 0x00,                         // 202: V9 = right score
+    0x64, 0x01,                         // 204: V4 = ball dx, serving right
+    0x22, 0xa4,                         // 206: set up the field
+    0x60, 0x02,                         // 208: V0 = 2
+    0xf0, 0x15,                         // 20a: delay = V0
+    0xf0, 0x07,                         // 20c: V0 = delay
+    0x30, 0x00,                         // 20e: skip if it ran out
+    0x12, 0x0c,                         // 210: keep waiting
+    0xa2, 0xde,                         // 212: I = paddle
+    0x60, 0x02,                         // 214: V0 = left paddle x
+    0xd0, 0x66,                         // 216: erase the left paddle
+    0x60, 0x01,                         // 218: key 1 moves up
+    0xe0, 0xa1,                         // 21a: skip unless held
+    0x22, 0xc6,                         // 21c: move up
+    0x60, 0x04,                         // 21e: key 4 moves down
+    0xe0, 0xa1,                         // 220: skip unless held
+    0x22, 0xcc,                         // 222: move down
+    0x60, 0x02,                         // 224: V0 = left paddle x
+    0xd0, 0x66,                         // 226: draw the left paddle
+    0x60, 0x3d,                         // 228: V0 = right paddle x
+    0xd0, 0x76,                         // 22a: erase the right paddle
+    0x60, 0x01,                         // 22c: V0 = 1
+    0x8e, 0x03,                         // 22e: VE ^= 1, the computer moves every other time
+    0x4e, 0x00,                         // 230: skip if it is its turn
+    0x12, 0x48,                         // 232: not this time
+    0x80, 0x70,                         // 234: V0 = right paddle y
+    0x70, 0x02,                         // 236: V0 += 2, its middle
+    0x81, 0x30,                         // 238: V1 = ball y
+    0x81, 0x05,                         // 23a: V1 -= V0, VF = ball at or below the middle
+    0x3f, 0x01,                         // 23c: skip if below
+    0x12, 0x46,                         // 23e: above, go up
+    0x31, 0x00,                         // 240: skip if level
+    0x22, 0xd8,                         // 242: below, go down
+    0x12, 0x48,                         // 244: moved
+    0x22, 0xd2,                         // 246: go up
+    0x60, 0x3d,                         // 248: V0 = right paddle x
+    0xd0, 0x76,                         // 24a: draw the right paddle
+    0xa2, 0xe4,                         // 24c: I = ball
+    0xd2, 0x31,                         // 24e: erase the ball
+    0x82, 0x44,                         // 250: x += dx
+    0x83, 0x54,                         // 252: y += dy
+    0x43, 0x00,                         // 254: skip unless at the top
+    0x65, 0x01,                         // 256: dy = 1
+    0x43, 0x1f,                         // 258: skip unless at the bottom
+    0x65, 0xff,                         // 25a: dy = -1
+    0x42, 0x00,                         // 25c: skip unless past the left paddle
+    0x12, 0x7a,                         // 25e: right scores
+    0x42, 0x3f,                         // 260: skip unless past the right paddle
+    0x12, 0x82,                         // 262: left scores
+    0xd2, 0x31,                         // 264: draw the ball
+    0x3f, 0x00,                         // 266: skip if it hit nothing
+    0x22, 0x6c,                         // 268: it hit a paddle
+    0x12, 0x08,                         // 26a: next frame
+    0xd2, 0x31,                         // 26c: erase the ball again
+    0x60, 0x00,                         // 26e: V0 = 0
+    0x80, 0x45,                         // 270: V0 = -dx
+    0x84, 0x00,                         // 272: dx = V0
+    0x82, 0x44,                         // 274: back where it came from
+    0xd2, 0x31,                         // 276: draw the ball
+    0x00, 0xee,                         // 278: return
+    0x79, 0x01,                         // 27a: right score += 1
+    0x64, 0x01,                         // 27c: serve to the right
+    0x22, 0x8a,                         // 27e: show the score
+    0x12, 0x08,                         // 280: play on
+    0x78, 0x01,                         // 282: left score += 1
+    0x64, 0xff,                         // 284: serve to the left
+    0x22, 0x8a,                         // 286: show the score
+    0x12, 0x08,                         // 288: play on
+    0x00, 0xe0,                         // 28a: clear the screen
+    0x6d, 0x14,                         // 28c: VD = x
+    0x6e, 0x0c,                         // 28e: VE = y
+    0xf8, 0x29,                         // 290: I = left score
+    0xdd, 0xe5,                         // 292: draw it
+    0x6d, 0x27,                         // 294: VD = x
+    0xf9, 0x29,                         // 296: I = right score
+    0xdd, 0xe5,                         // 298: draw it
+    0x60, 0x3c,                         // 29a: V0 = a second
+    0xf0, 0x15,                         // 29c: delay = V0
+    0xf0, 0x07,                         // 29e: V0 = delay
+    0x30, 0x00,                         // 2a0: skip if it ran out
+    0x12, 0x9e,                         // 2a2: keep waiting
+    0x00, 0xe0,                         // 2a4: clear the screen
+    0x62, 0x1f,                         // 2a6: ball x = 31
+    0xc3, 0x0f,                         // 2a8: ball y = 8 to 23, at random
+    0x73, 0x08,                         // 2aa: ball y += 8
+    0xc5, 0x01,                         // 2ac: dy = 0 or 1
+    0x35, 0x01,                         // 2ae: skip if 1
+    0x65, 0xff,                         // 2b0: dy = -1
+    0x66, 0x0d,                         // 2b2: left paddle y = 13
+    0x67, 0x0d,                         // 2b4: right paddle y = 13
+    0xa2, 0xde,                         // 2b6: I = paddle
+    0x60, 0x02,                         // 2b8: V0 = left paddle x
+    0xd0, 0x66,                         // 2ba: draw the left paddle
+    0x60, 0x3d,                         // 2bc: V0 = right paddle x
+    0xd0, 0x76,                         // 2be: draw the right paddle
+    0xa2, 0xe4,                         // 2c0: I = ball
+    0xd2, 0x31,                         // 2c2: draw the ball
+    0x00, 0xee,                         // 2c4: return
+    0x36, 0x00,                         // 2c6: skip if at the top
+    0x76, 0xff,                         // 2c8: up one
+    0x00, 0xee,                         // 2ca: return
+    0x36, 0x1a,                         // 2cc: skip if at the bottom
+    0x76, 0x01,                         // 2ce: down one
+    0x00, 0xee,                         // 2d0: return
+    0x37, 0x00,                         // 2d2: skip if at the top
+    0x77, 0xff,                         // 2d4: up one
+    0x00, 0xee,                         // 2d6: return
+    0x37, 0x1a,                         // 2d8: skip if at the bottom
+    0x77, 0x01,                         // 2da: down one
+    0x00, 0xee,                         // 2dc: return
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 2de: paddle
+    0x80,                               // 2e4: ball
+];
+
+/// Breakout: 4 and 6 move the paddle, four rows of bricks, three balls
+/// and the score shown at the end.
+pub const BREAKOUT: &[u8] = &[
+    0x6c, 0x00, // 200: VC = score
+    0x6d, 0x03, // 202: VD = lives
+    0x6e, 0x02, // 204: VE = brick row y
+    0x61, 0x00, // 206: V1 = brick x
+    0xa2, 0xdc, // 208: I = brick
+    0xd1, 0xe2, // 20a: draw a brick
+    0x71, 0x04, // 20c: x += 4
+    0x31, 0x40, // 20e: skip at the right edge
+    0x12, 0x0a, // 210: next brick
+    0x7e, 0x04, // 212: y += 4
+    0x3e, 0x12, // 214: skip after four rows
+    0x12, 0x06, // 216: next row
+    0x6a, 0x1c, // 218: VA = paddle x
+    0x6b, 0x1e, // 21a: VB = paddle y
+    0xa2, 0xda, // 21c: I = paddle
+    0xda, 0xb1, // 21e: draw the paddle
+    0x22, 0x9c, // 220: put the ball in play
+    0x60, 0x02, // 222: V0 = 2
+    0xf0, 0x15, // 224: delay = V0
+    0xf0, 0x07, // 226: V0 = delay
+    0x30, 0x00, // 228: skip if it ran out
+    0x12, 0x26, // 22a: keep waiting
+    0xa2, 0xda, // 22c: I = paddle
+    0xda, 0xb1, // 22e: erase the paddle
+    0x60, 0x04, // 230: key 4 moves left
+    0xe0, 0xa1, // 232: skip unless held
+    0x22, 0xce, // 234: move left
+    0x60, 0x06, // 236: key 6 moves right
+    0xe0, 0xa1, // 238: skip unless held
+    0x22, 0xd4, // 23a: move right
+    0xda, 0xb1, // 23c: draw the paddle
+    0xa2, 0xdb, // 23e: I = ball
+    0xd2, 0x31, // 240: erase the ball
+    0x82, 0x44, // 242: x += dx
+    0x83, 0x54, // 244: y += dy
+    0x42, 0x00, // 246: skip unless at the left wall
+    0x64, 0x01, // 248: dx = 1
+    0x42, 0x3f, // 24a: skip unless at the right wall
+    0x64, 0xff, // 24c: dx = -1
+    0x43, 0x00, // 24e: skip unless at the top
+    0x65, 0x01, // 250: dy = 1
+    0x33, 0x1f, // 252: skip if it fell past the paddle
+    0x12, 0x62, // 254: still in play
+    0x7d, 0xff, // 256: lives -= 1
+    0x3d, 0x00, // 258: skip if none are left
+    0x12, 0x5e, // 25a: serve again
+    0x12, 0xb0, // 25c: game over
+    0x22, 0x9c, // 25e: serve again
+    0x12, 0x22, // 260: play on
+    0xd2, 0x31, // 262: draw the ball
+    0x3f, 0x00, // 264: skip if it hit nothing
+    0x22, 0x6a, // 266: the paddle or a brick
+    0x12, 0x22, // 268: next frame
+    0xd2, 0x31, // 26a: erase the ball again
+    0x60, 0x00, // 26c: V0 = 0
+    0x80, 0x55, // 26e: V0 -= dy
+    0x85, 0x00, // 270: dy = V0, turned around
+    0x43, 0x1e, // 272: skip unless on the paddle row
+    0x12, 0x92, // 274: off the paddle
+    0x86, 0x20, // 276: V6 = x
+    0x60, 0xfc, // 278: V0 = fc
+    0x86, 0x02, // 27a: V6 = x of the brick
+    0x87, 0x30, // 27c: V7 = y
+    0x87, 0x02, // 27e: V7 = y rounded down to 4
+    0x60, 0x02, // 280: V0 = 2
+    0x87, 0x01, // 282: V7 = y of the brick
+    0xa2, 0xdc, // 284: I = brick
+    0xd6, 0x72, // 286: erase it
+    0x7c, 0x01, // 288: score += 1
+    0x83, 0x54, // 28a: y += dy, back out
+    0xa2, 0xdb, // 28c: I = ball
+    0xd2, 0x31, // 28e: draw the ball
+    0x00, 0xee, // 290: return
+    0x65, 0xff, // 292: dy = -1
+    0x83, 0x54, // 294: y += dy, back up
+    0xa2, 0xdb, // 296: I = ball
+    0xd2, 0x31, // 298: draw the ball
+    0x00, 0xee, // 29a: return
+    0x82, 0xa0, // 29c: ball x = paddle x
+    0x72, 0x03, // 29e: ball x += 3
+    0x63, 0x1d, // 2a0: ball y = 29
+    0x65, 0xff, // 2a2: dy = -1
+    0xc4, 0x01, // 2a4: dx = 0 or 1
+    0x34, 0x01, // 2a6: skip if 1
+    0x64, 0xff, // 2a8: dx = -1
+    0xa2, 0xdb, // 2aa: I = ball
+    0xd2, 0x31, // 2ac: draw the ball
+    0x00, 0xee, // 2ae: return
+    0x00, 0xe0, // 2b0: clear the screen
+    0xa3, 0x00, // 2b2: I = 300
+    0xfc, 0x33, // 2b4: score as decimal
+    0xf2, 0x65, // 2b6: V0-V2 = digits
+    0x6d, 0x18, // 2b8: VD = x
+    0x6e, 0x0d, // 2ba: VE = y
+    0xf0, 0x29, // 2bc: I = hundreds
+    0xdd, 0xe5, // 2be: draw
+    0x7d, 0x05, // 2c0: x += 5
+    0xf1, 0x29, // 2c2: I = tens
+    0xdd, 0xe5, // 2c4: draw
+    0x7d, 0x05, // 2c6: x += 5
+    0xf2, 0x29, // 2c8: I = ones
+    0xdd, 0xe5, // 2ca: draw
+    0x12, 0xcc, // 2cc: done
+    0x3a, 0x00, // 2ce: skip if at the left edge
+    0x7a, 0xfe, // 2d0: left two
+    0x00, 0xee, // 2d2: return
+    0x3a, 0x38, // 2d4: skip if at the right edge
+    0x7a, 0x02, // 2d6: right two
+    0x00, 0xee, // 2d8: return
+    0xff,       // 2da: paddle
+    0x80,       // 2db: ball
+    0xe0, 0xe0, // 2dc: brick
+];
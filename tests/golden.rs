@@ -0,0 +1,126 @@
+//! Golden-image tests: each game in `tests/games` plays its recorded movie
+//! and the screen checksum is compared at a few frames against values taken
+//! when the game was known to play right. A change to sprite clipping,
+//! collision or the timers that changes how a game plays ends up here.
+//!
+//! When a change is meant to alter gameplay, check the new screens by eye,
+//! e.g. with `--headless --movie tests/games/pong.movie --frames N
+//! --screenshot`, and copy the new checksums from the failure.
+
+mod games;
+
+use chip8_emulator::blocks::Backend;
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::movie::Movie;
+use chip8_emulator::timing::Timing;
+
+// enough for every game loop to finish within a frame
+const IPF: u32 = 30;
+
+/// Plays `rom` with `movie` and returns the screen checksum after each of
+/// the given numbers of frames.
+fn play(rom: &[u8], movie: &str, backend: Backend, frames: &[u64]) -> Vec<(u64, u64)> {
+    let movie = Movie::parse(movie).unwrap();
+    let mut chip8 = Chip8State::builder().rom(rom).seed(movie.seed).build();
+    chip8.timing = Timing::Ipf(IPF);
+    chip8.backend = backend;
+    chip8.movie = Some(movie);
+
+    let mut checksums = Vec::new();
+    while chip8.frame < frames.iter().copied().max().unwrap_or(0) {
+        chip8.set_keys([false; 16]);
+        chip8.run_frame().unwrap();
+        if frames.contains(&chip8.frame) {
+            checksums.push((chip8.frame, chip8.screen.checksum()));
+        }
+    }
+
+    checksums
+}
+
+// both backends have to play the game the same way
+fn check(rom: &[u8], movie: &str, expected: &[(u64, u64)]) {
+    let frames: Vec<u64> = expected.iter().map(|&(frame, _)| frame).collect();
+
+    for backend in [Backend::Interpreter, Backend::Blocks] {
+        let actual = play(rom, movie, backend, &frames);
+        let wrong: Vec<String> = expected.iter().zip(&actual)
+            .filter(|(expected, actual)| expected != actual)
+            .map(|(_, (frame, checksum))| format!("({}, {:#018x})", frame, checksum))
+            .collect();
+        assert!(wrong.is_empty(), "{:?}: the screen changed at: {}", backend, wrong.join(", "));
+    }
+}
+
+#[test]
+fn snake() {
+    check(games::SNAKE, include_str!("games/snake.movie"), &[
+        // moving right
+        (100, 0xa875801cc4a11831),
+        // turned up after the food, and grown
+        (160, 0x6d7001739b78068b),
+        // on the way down to the second food
+        (300, 0xd2eb1b207466ffe8),
+        // ran into itself, the score is 002
+        (340, 0x62f34402cbe02ee7),
+    ]);
+}
+
+#[test]
+fn pong() {
+    check(games::PONG, include_str!("games/pong.movie"), &[
+        (60, 0xe7c18b9ed09dd0ca),
+        // the computer missed, 1 to 0
+        (120, 0x1adecd8d9143e835),
+        // and so did the player, 1 to 1
+        (270, 0xaa499bbff404ea65),
+        // back off the right paddle
+        (420, 0x5620c5b4b0f0d344),
+        // 1 to 2
+        (600, 0x325912b048b59315),
+    ]);
+}
+
+#[test]
+fn breakout() {
+    check(games::BREAKOUT, include_str!("games/breakout.movie"), &[
+        // the paddle moved left under the ball
+        (40, 0x5efb61167a95e14a),
+        // back up off the paddle, one brick gone
+        (90, 0x1cb5a8f4642d6824),
+        // stuck between two rows of bricks
+        (300, 0xafbf964fb99794ba),
+        (600, 0xd33c88dcb1de3a4c),
+        // out of balls, the score is 057
+        (900, 0xb1019d203404068f),
+    ]);
+}
+
+#[test]
+fn movie_records_what_it_plays() {
+    let text = include_str!("games/pong.movie");
+    let mut player = Movie::parse(text).unwrap();
+    let mut recorder = Movie::record(player.seed);
+
+    for frame in 0..600 {
+        let keys = player.input(frame, [false; 16]);
+        // set twice in a frame, only the last keys count
+        recorder.input(frame, [true; 16]);
+        recorder.input(frame, keys);
+    }
+
+    let mut written = Vec::new();
+    recorder.write(&mut written).unwrap();
+    let replayed = Movie::parse(&String::from_utf8(written).unwrap()).unwrap();
+    assert_eq!(replayed.seed, 7);
+    assert_eq!(replayed.length(), player.length());
+    assert!((0..600).all(|frame| replayed.keys(frame) == player.keys(frame)));
+}
+
+#[test]
+fn movie_errors() {
+    assert!(Movie::parse("").is_err());
+    assert!(Movie::parse("chip8 movie\n0 0000\n").is_err());
+    assert!(Movie::parse("chip8 movie\nseed 1\n10 0001\n5 0000\n").is_err());
+    assert!(Movie::parse("chip8 movie\nseed 1\n10 up\n").is_err());
+}
@@ -0,0 +1,324 @@
+//! One or more tests for every instruction, each running a single opcode on
+//! a machine set up with `Chip8State::builder`.
+
+use chip8_emulator::chip8::Chip8State;
+
+fn run(chip8: Chip8State, opcode: u16) -> Chip8State {
+    let mut chip8 = chip8;
+    chip8.execute(opcode).unwrap();
+    chip8
+}
+
+fn lit(chip8: &Chip8State) -> usize {
+    chip8.screen.pixels().filter(|&pixel| pixel != 0).count()
+}
+
+#[test]
+fn clear_screen_00e0() {
+    let mut chip8 = run(Chip8State::builder().memory(0x300, &[0xff]).i(0x300).build(), 0xd011);
+    assert_eq!(lit(&chip8), 8);
+
+    chip8.execute(0x00e0).unwrap();
+    assert_eq!(lit(&chip8), 0);
+    assert_eq!(chip8.pc, 0x204);
+}
+
+#[test]
+fn return_00ee() {
+    let chip8 = run(Chip8State::builder().pc(0x400).stack(&[0x20a]).build(), 0x00ee);
+
+    assert_eq!(chip8.pc, 0x20c);
+    assert_eq!(chip8.sp, 0);
+    assert!(chip8.stack.is_empty());
+}
+
+#[test]
+fn lores_00fe_and_hires_00ff() {
+    let chip8 = run(Chip8State::builder().build(), 0x00ff);
+    assert_eq!((chip8.screen.width(), chip8.screen.height()), (128, 64));
+
+    let chip8 = run(Chip8State::builder().hires().build(), 0x00fe);
+    assert_eq!((chip8.screen.width(), chip8.screen.height()), (64, 32));
+}
+
+#[test]
+fn jump_1nnn() {
+    let chip8 = run(Chip8State::builder().build(), 0x1abc);
+    assert_eq!(chip8.pc, 0xabc);
+}
+
+#[test]
+fn call_2nnn() {
+    let chip8 = run(Chip8State::builder().pc(0x210).build(), 0x2abc);
+
+    assert_eq!(chip8.pc, 0xabc);
+    assert_eq!(chip8.sp, 1);
+    assert_eq!(chip8.stack, [0x210]);
+}
+
+#[test]
+fn skip_if_equal_3xnn() {
+    assert_eq!(run(Chip8State::builder().v(3, 0x42).build(), 0x3342).pc, 0x204);
+    assert_eq!(run(Chip8State::builder().v(3, 0x41).build(), 0x3342).pc, 0x202);
+}
+
+#[test]
+fn skip_if_not_equal_4xnn() {
+    assert_eq!(run(Chip8State::builder().v(3, 0x42).build(), 0x4342).pc, 0x202);
+    assert_eq!(run(Chip8State::builder().v(3, 0x41).build(), 0x4342).pc, 0x204);
+}
+
+#[test]
+fn skip_if_registers_equal_5xy0() {
+    assert_eq!(run(Chip8State::builder().v(1, 7).v(2, 7).build(), 0x5120).pc, 0x204);
+    assert_eq!(run(Chip8State::builder().v(1, 7).v(2, 8).build(), 0x5120).pc, 0x202);
+}
+
+#[test]
+fn set_6xnn() {
+    let chip8 = run(Chip8State::builder().build(), 0x6a42);
+    assert_eq!(chip8.v[0xa], 0x42);
+}
+
+#[test]
+fn add_7xnn_wraps_without_carry() {
+    let chip8 = run(Chip8State::builder().v(0, 0xff).v(0xf, 5).build(), 0x7002);
+
+    assert_eq!(chip8.v[0], 0x01);
+    assert_eq!(chip8.v[0xf], 5);
+}
+
+#[test]
+fn copy_8xy0() {
+    let chip8 = run(Chip8State::builder().v(1, 0x42).build(), 0x8010);
+    assert_eq!(chip8.v[0], 0x42);
+}
+
+#[test]
+fn or_8xy1_and_8xy2_xor_8xy3_reset_vf() {
+    let machine = || Chip8State::builder().v(0, 0xf0).v(1, 0x3c).v(0xf, 1).build();
+
+    let chip8 = run(machine(), 0x8011);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0xfc, 0));
+    let chip8 = run(machine(), 0x8012);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x30, 0));
+    let chip8 = run(machine(), 0x8013);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0xcc, 0));
+}
+
+#[test]
+fn add_8xy4_sets_carry() {
+    let chip8 = run(Chip8State::builder().v(0, 0x10).v(1, 0x20).build(), 0x8014);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x30, 0));
+
+    let chip8 = run(Chip8State::builder().v(0, 0xff).v(1, 0x01).build(), 0x8014);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x00, 1));
+
+    // the flag is written last, so it wins when VF is the target
+    let chip8 = run(Chip8State::builder().v(0xf, 0xff).v(1, 0x02).build(), 0x8f14);
+    assert_eq!(chip8.v[0xf], 1);
+}
+
+#[test]
+fn subtract_8xy5_clears_vf_on_borrow() {
+    let chip8 = run(Chip8State::builder().v(0, 0x30).v(1, 0x10).build(), 0x8015);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x20, 1));
+
+    let chip8 = run(Chip8State::builder().v(0, 0x10).v(1, 0x30).build(), 0x8015);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0xe0, 0));
+}
+
+#[test]
+fn subtract_8xy5_of_equal_values_does_not_borrow() {
+    let chip8 = run(Chip8State::builder().v(0, 0x42).v(1, 0x42).build(), 0x8015);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x00, 1));
+}
+
+#[test]
+fn shift_right_8xy6_shifts_vy() {
+    let chip8 = run(Chip8State::builder().v(0, 0xff).v(1, 0x05).build(), 0x8016);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x02, 1));
+
+    let chip8 = run(Chip8State::builder().v(1, 0x04).build(), 0x8016);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x02, 0));
+}
+
+#[test]
+fn subtract_8xy7_the_other_way() {
+    let chip8 = run(Chip8State::builder().v(0, 0x10).v(1, 0x30).build(), 0x8017);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x20, 1));
+
+    let chip8 = run(Chip8State::builder().v(0, 0x30).v(1, 0x10).build(), 0x8017);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0xe0, 0));
+
+    // no borrow when they are equal, even though VX is 0 by the time it would be checked
+    let chip8 = run(Chip8State::builder().v(0, 0x30).v(1, 0x30).build(), 0x8017);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x00, 1));
+}
+
+#[test]
+fn shift_left_8xye_shifts_vy() {
+    let chip8 = run(Chip8State::builder().v(1, 0x81).build(), 0x801e);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x02, 1));
+
+    let chip8 = run(Chip8State::builder().v(1, 0x41).build(), 0x801e);
+    assert_eq!((chip8.v[0], chip8.v[0xf]), (0x82, 0));
+}
+
+#[test]
+fn skip_if_registers_differ_9xy0() {
+    assert_eq!(run(Chip8State::builder().v(1, 7).v(2, 7).build(), 0x9120).pc, 0x202);
+    assert_eq!(run(Chip8State::builder().v(1, 7).v(2, 8).build(), 0x9120).pc, 0x204);
+}
+
+#[test]
+fn set_i_annn() {
+    let chip8 = run(Chip8State::builder().build(), 0xa123);
+    assert_eq!(chip8.i, 0x123);
+}
+
+#[test]
+fn jump_with_offset_bnnn_adds_v0() {
+    let chip8 = run(Chip8State::builder().v(0, 4).v(3, 8).build(), 0xb300);
+    assert_eq!(chip8.pc, 0x304);
+}
+
+#[test]
+fn random_cxnn_is_masked() {
+    for _ in 0..32 {
+        assert_eq!(run(Chip8State::builder().build(), 0xc000).v[0], 0);
+        assert_eq!(run(Chip8State::builder().build(), 0xc00f).v[0] & 0xf0, 0);
+    }
+}
+
+#[test]
+fn draw_dxyn() {
+    let chip8 = run(Chip8State::builder().v(0, 4).v(1, 2).i(0x300).memory(0x300, &[0x81, 0xff]).build(), 0xd012);
+
+    assert_eq!(lit(&chip8), 10);
+    assert_eq!(chip8.screen.pixel(4, 2), 1);
+    assert_eq!(chip8.screen.pixel(11, 2), 1);
+    assert_eq!(chip8.screen.pixel(5, 2), 0);
+    assert!((4..12).all(|x| chip8.screen.pixel(x, 3) == 1));
+    assert_eq!(chip8.v[0xf], 0);
+}
+
+#[test]
+fn draw_dxyn_collides() {
+    let mut chip8 = Chip8State::builder().i(0x300).memory(0x300, &[0xff]).build();
+    chip8.execute(0xd011).unwrap();
+    chip8.execute(0xd011).unwrap();
+
+    assert_eq!(lit(&chip8), 0);
+    assert_eq!(chip8.v[0xf], 1);
+}
+
+#[test]
+fn draw_dxyn_wraps_the_start_and_clips_the_rest() {
+    let chip8 = run(Chip8State::builder().v(0, 64 + 60).v(1, 32 + 31).i(0x300).memory(0x300, &[0xff, 0xff]).build(), 0xd012);
+
+    assert_eq!(lit(&chip8), 4);
+    assert!((60..64).all(|x| chip8.screen.pixel(x, 31) == 1));
+}
+
+#[test]
+fn skip_if_key_ex9e() {
+    assert_eq!(run(Chip8State::builder().v(2, 0xa).key(0xa).build(), 0xe29e).pc, 0x204);
+    assert_eq!(run(Chip8State::builder().v(2, 0xa).build(), 0xe29e).pc, 0x202);
+}
+
+#[test]
+fn skip_if_not_key_exa1() {
+    assert_eq!(run(Chip8State::builder().v(2, 0xa).key(0xa).build(), 0xe2a1).pc, 0x202);
+    assert_eq!(run(Chip8State::builder().v(2, 0xa).build(), 0xe2a1).pc, 0x204);
+}
+
+#[test]
+fn read_delay_fx07() {
+    let chip8 = run(Chip8State::builder().delay(0x33).build(), 0xf507);
+    assert_eq!(chip8.v[5], 0x33);
+}
+
+#[test]
+fn wait_for_key_fx0a() {
+    let chip8 = run(Chip8State::builder().build(), 0xf50a);
+    assert_eq!(chip8.pc, 0x200);
+    assert!(chip8.waiting_for_key);
+
+    let chip8 = run(Chip8State::builder().key(0xc).build(), 0xf50a);
+    assert_eq!(chip8.pc, 0x202);
+    assert_eq!(chip8.v[5], 0xc);
+}
+
+#[test]
+fn set_delay_fx15() {
+    let chip8 = run(Chip8State::builder().v(5, 0x44).build(), 0xf515);
+    assert_eq!(chip8.delay, 0x44);
+}
+
+#[test]
+fn set_sound_fx18_carries_on() {
+    let chip8 = run(Chip8State::builder().v(5, 0x44).build(), 0xf518);
+    assert_eq!(chip8.pc, 0x202);
+}
+
+#[test]
+fn add_to_i_fx1e() {
+    let chip8 = run(Chip8State::builder().i(0x2ff).v(5, 0x02).v(0xf, 7).build(), 0xf51e);
+
+    assert_eq!(chip8.i, 0x301);
+    assert_eq!(chip8.v[0xf], 7);
+}
+
+#[test]
+fn font_fx29() {
+    let chip8 = run(Chip8State::builder().v(5, 0xa).build(), 0xf529);
+    assert_eq!(chip8.i, 50);
+    assert_eq!(chip8.memory()[chip8.i as usize..chip8.i as usize + 5], [0xf0, 0x90, 0xf0, 0x90, 0x90]);
+
+    // 0x3a * 5 does not fit in a byte, only the low digit is used
+    let chip8 = run(Chip8State::builder().v(5, 0x3a).build(), 0xf529);
+    assert_eq!(chip8.i, 50);
+}
+
+#[test]
+fn decimal_fx33() {
+    let chip8 = run(Chip8State::builder().v(5, 254).i(0x300).build(), 0xf533);
+
+    assert_eq!(chip8.memory()[0x300..0x303], [2, 5, 4]);
+    assert_eq!(chip8.i, 0x300);
+}
+
+#[test]
+fn store_fx55() {
+    let chip8 = run(Chip8State::builder().v(0, 1).v(1, 2).v(2, 3).v(3, 4).i(0x300).build(), 0xf255);
+
+    assert_eq!(chip8.memory()[0x300..0x304], [1, 2, 3, 0]);
+    assert_eq!(chip8.i, 0x303);
+}
+
+#[test]
+fn store_fx55_over_code() {
+    // V0-V1 is 6005, written over the 6001 the program is about to run
+    let rom = [0xa2, 0x06, 0xf1, 0x55, 0x12, 0x06, 0x60, 0x01];
+    let mut chip8 = Chip8State::builder().rom(&rom).v(0, 0x60).v(1, 0x05).build();
+    // run 6001 once first, so it has been decoded before it changes
+    chip8.pc = 0x206;
+    chip8.step().unwrap();
+    assert_eq!(chip8.v[0], 0x01);
+
+    chip8.pc = 0x200;
+    chip8.v[0] = 0x60;
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.v[0], 0x05);
+}
+
+#[test]
+fn load_fx65() {
+    let chip8 = run(Chip8State::builder().memory(0x300, &[1, 2, 3, 4]).i(0x300).build(), 0xf265);
+
+    assert_eq!(chip8.v[0..4], [1, 2, 3, 0]);
+    assert_eq!(chip8.i, 0x303);
+}
@@ -0,0 +1,41 @@
+//! The profiler's frame budget: a frame is only over budget when it had
+//! more to run than the budget leaves room for.
+
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::profile::Profiler;
+use chip8_emulator::timing::{self, Timing};
+
+fn over_budget(rom: &[u8], timing: Timing, budget: u32, unit: &'static str) -> String {
+    let mut chip8 = Chip8State::builder().rom(rom).build();
+    chip8.timing = timing;
+    chip8.display_wait = true;
+    chip8.profiler = Some(Profiler::new(budget, unit));
+    for _ in 0..3 {
+        chip8.run_frame().unwrap();
+    }
+
+    let mut report = Vec::new();
+    chip8.profiler.unwrap().report(&mut report).unwrap();
+    String::from_utf8(report).unwrap().lines().nth(1).unwrap().to_string()
+}
+
+// draws, then waits for the next frame: 1 instruction in the first frame and 2 after
+const LIGHT: [u8; 4] = [0xd0, 0x01, 0x12, 0x00];
+// never waits
+const BUSY: [u8; 2] = [0x12, 0x00];
+
+#[test]
+fn light_frames_are_not_over_budget() {
+    assert_eq!(over_budget(&LIGHT, Timing::Ipf(10), 10, "instructions"), "0 frames needed more than the budget of 10 instructions");
+    // with exactly enough room
+    assert_eq!(over_budget(&LIGHT, Timing::Ipf(10), 2, "instructions"), "0 frames needed more than the budget of 2 instructions");
+    assert_eq!(over_budget(&LIGHT, Timing::Vip, timing::VIP_BUDGET, "cycles"), format!("0 frames needed more than the budget of {} cycles", timing::VIP_BUDGET));
+}
+
+#[test]
+fn frames_that_run_out_of_room_are_over_budget() {
+    assert_eq!(over_budget(&BUSY, Timing::Ipf(10), 10, "instructions"), "3 frames needed more than the budget of 10 instructions, first 3: 0 1 2");
+    assert_eq!(over_budget(&BUSY, Timing::Vip, timing::VIP_BUDGET, "cycles"), format!("3 frames needed more than the budget of {} cycles, first 3: 0 1 2", timing::VIP_BUDGET));
+    // a budget below what the light frames take
+    assert_eq!(over_budget(&LIGHT, Timing::Ipf(10), 1, "instructions"), "2 frames needed more than the budget of 1 instructions, first 2: 1 2");
+}
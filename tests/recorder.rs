@@ -0,0 +1,49 @@
+//! Recordings as a video player would read them back.
+
+use chip8_emulator::framebuffer::Framebuffer;
+use chip8_emulator::palette::Palette;
+use chip8_emulator::recorder::{Format, Recorder};
+use std::fs;
+
+fn record(format: Format, screens: &[Framebuffer]) -> Vec<u8> {
+    let path = std::env::temp_dir().join(format!("chip8-recording-{}-{:?}", std::process::id(), format));
+    let path = path.to_str().unwrap();
+    let mut recorder = Recorder::create(path, format, Palette::default(), 2, &screens[0]).unwrap();
+    for screen in screens {
+        recorder.frame(screen).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    let bytes = fs::read(path).unwrap();
+    fs::remove_file(path).unwrap();
+    bytes
+}
+
+#[test]
+fn y4m_has_a_header_and_three_full_planes_a_frame() {
+    let mut lit = Framebuffer::new();
+    lit.flip(0, 0, 0);
+    let y4m = record(Format::Y4m, &[Framebuffer::new(), lit]);
+
+    let header = b"YUV4MPEG2 W128 H64 F60:1 Ip A1:1 C444\n";
+    assert_eq!(y4m[..header.len()], header[..]);
+    let plane = 128 * 64;
+    let frames: Vec<&[u8]> = y4m[header.len()..].chunks(6 + 3 * plane).collect();
+    assert_eq!(frames.len(), 2);
+
+    for frame in &frames {
+        assert_eq!(frame.len(), 6 + 3 * plane);
+        assert_eq!(frame[..6], *b"FRAME\n");
+    }
+    // black and white are the ends of studio swing, with no colour either way
+    let (y, u, v) = (&frames[1][6..6 + plane], &frames[1][6 + plane..6 + 2 * plane], &frames[1][6 + 2 * plane..]);
+    assert_eq!([y[0], y[1], y[128], y[129], y[2]], [235, 235, 235, 235, 16]);
+    assert!(u.iter().chain(v).all(|&channel| channel == 128));
+    assert!(frames[0][6..6 + plane].iter().all(|&luma| luma == 16));
+}
+
+#[test]
+fn rgb_is_bare_frames() {
+    let rgb = record(Format::Rgb, &[Framebuffer::new(), Framebuffer::new(), Framebuffer::new()]);
+    assert_eq!(rgb.len(), 3 * 128 * 64 * 3);
+}
@@ -0,0 +1,234 @@
+//! A second chip-8, written to be read rather than to be fast. Every
+//! instruction is a few lines straight out of the spec, with the same quirks
+//! the emulator picked: the VIP's VF reset for 8XY1-8XY3, shifts of VY, I
+//! moving on after FX55 and FX65, and sprites clipped at the edges.
+
+use chip8_emulator::chip8::{Chip8State, Fault, STACK_SIZE};
+
+pub struct Machine {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub stack: Vec<u16>,
+    pub delay: u8,
+    pub memory: Vec<u8>,
+    pub keys: [bool; 16],
+    pub pressed: [bool; 16],
+    pub hires: bool,
+    // rows of the 128x64 screen, only the top left 64x32 is used in low resolution
+    pub screen: Vec<[bool; 128]>,
+}
+
+impl Machine {
+    /// A copy of everything the instructions can see in `chip8`.
+    pub fn from(chip8: &Chip8State) -> Self {
+        let mut screen = vec![[false; 128]; 64];
+        for (y, row) in screen.iter_mut().enumerate().take(chip8.screen.height()) {
+            for (x, pixel) in row.iter_mut().enumerate().take(chip8.screen.width()) {
+                *pixel = chip8.screen.pixel(x, y) != 0;
+            }
+        }
+
+        Machine {
+            v: chip8.v,
+            i: chip8.i,
+            pc: chip8.pc,
+            stack: chip8.stack.clone(),
+            delay: chip8.delay,
+            memory: chip8.memory().to_vec(),
+            keys: chip8.keys,
+            pressed: chip8.pressed,
+            hires: chip8.screen.width() == 128,
+            screen,
+        }
+    }
+
+    fn width(&self) -> usize {
+        if self.hires {128} else {64}
+    }
+
+    fn height(&self) -> usize {
+        if self.hires {64} else {32}
+    }
+
+    // `length` bytes from I, or the fault for going past the end of memory
+    fn data(&self, length: usize) -> Result<usize, Fault> {
+        let start = self.i as usize;
+        if start + length > 4096 {
+            return Err(Fault::MemoryOutOfRange {pc: self.pc, address: (start + length - 1).max(start)});
+        }
+        Ok(start)
+    }
+
+    /// Runs the instruction at `pc`. Nothing changes when it faults.
+    pub fn step(&mut self) -> Result<(), Fault> {
+        let pc = self.pc as usize;
+        if pc + 2 > 4096 {
+            return Err(Fault::MemoryOutOfRange {pc: self.pc, address: pc + 1});
+        }
+        let opcode = u16::from_be_bytes([self.memory[pc], self.memory[pc + 1]]);
+
+        let x = (opcode >> 8 & 0xf) as usize;
+        let y = (opcode >> 4 & 0xf) as usize;
+        let n = (opcode & 0xf) as usize;
+        let nn = (opcode & 0xff) as u8;
+        let nnn = opcode & 0xfff;
+        let vx = self.v[x];
+        let vy = self.v[y];
+        let mut next = self.pc.wrapping_add(2);
+
+        match (opcode >> 12, nn) {
+            (0x0, 0xe0) => self.screen = vec![[false; 128]; 64],
+            (0x0, 0xee) => next = self.stack.pop().ok_or(Fault::StackUnderflow {pc: self.pc})?.wrapping_add(2),
+            (0x0, 0xfe) | (0x0, 0xff) => {
+                self.hires = nn == 0xff;
+                self.screen = vec![[false; 128]; 64];
+            },
+            (0x0, _) => {},
+            (0x1, _) => next = nnn,
+            (0x2, _) => {
+                if self.stack.len() == STACK_SIZE {
+                    return Err(Fault::StackOverflow {pc: self.pc});
+                }
+                self.stack.push(self.pc);
+                next = nnn;
+            },
+            (0x3, _) => if vx == nn {next += 2},
+            (0x4, _) => if vx != nn {next += 2},
+            (0x5, _) => if vx == vy {next += 2},
+            (0x6, _) => self.v[x] = nn,
+            (0x7, _) => self.v[x] = vx.wrapping_add(nn),
+            (0x8, _) => {
+                let (result, flag) = match n {
+                    0x0 => (vy, None),
+                    0x1 => (vx | vy, Some(0)),
+                    0x2 => (vx & vy, Some(0)),
+                    0x3 => (vx ^ vy, Some(0)),
+                    0x4 => (vx.wrapping_add(vy), Some((vx as u16 + vy as u16 > 0xff) as u8)),
+                    0x5 => (vx.wrapping_sub(vy), Some((vx >= vy) as u8)),
+                    0x6 => (vy >> 1, Some(vy & 1)),
+                    0x7 => (vy.wrapping_sub(vx), Some((vy >= vx) as u8)),
+                    0xe => (vy << 1, Some(vy >> 7)),
+                    _ => (vx, None),
+                };
+                // the flag is written last, so it wins when X is F
+                self.v[x] = result;
+                if let Some(flag) = flag {
+                    self.v[0xf] = flag;
+                }
+            },
+            (0x9, _) => if vx != vy {next += 2},
+            (0xa, _) => self.i = nnn,
+            (0xb, _) => next = nnn + self.v[0] as u16,
+            (0xc, _) => panic!("CXNN is random, the comparison has to stop before it"),
+            (0xd, _) => {
+                let start = self.data(n)?;
+                let left = vx as usize % self.width();
+                let top = vy as usize % self.height();
+                let mut collided = false;
+
+                for row in 0..n {
+                    for column in 0..8 {
+                        let (x, y) = (left + column, top + row);
+                        if x >= self.width() || y >= self.height() {
+                            continue;
+                        }
+                        if self.memory[start + row] >> (7 - column) & 1 == 1 {
+                            collided |= self.screen[y][x];
+                            self.screen[y][x] = !self.screen[y][x];
+                        }
+                    }
+                }
+                self.v[0xf] = collided as u8;
+            },
+            (0xe, 0x9e) | (0xe, 0xa1) => {
+                if vx > 0xf {
+                    return Err(Fault::InvalidKey {pc: self.pc, key: vx});
+                }
+                if self.keys[vx as usize] == (nn == 0x9e) {
+                    next += 2;
+                }
+            },
+            (0xe, _) => {},
+            (0xf, 0x07) => self.v[x] = self.delay,
+            (0xf, 0x0a) => match self.pressed.iter().position(|&pressed| pressed) {
+                Some(key) => {
+                    self.v[x] = key as u8;
+                    self.pressed[key] = false;
+                },
+                None => next = self.pc,
+            },
+            (0xf, 0x15) => self.delay = vx,
+            (0xf, 0x1e) => self.i = self.i.wrapping_add(vx as u16),
+            (0xf, 0x29) => self.i = (vx & 0xf) as u16 * 5,
+            (0xf, 0x33) => {
+                let start = self.data(3)?;
+                self.memory[start] = vx / 100;
+                self.memory[start + 1] = vx / 10 % 10;
+                self.memory[start + 2] = vx % 10;
+            },
+            (0xf, 0x55) => {
+                let start = self.data(x + 1)?;
+                for register in 0..=x {
+                    self.memory[start + register] = self.v[register];
+                }
+                self.i = self.i.wrapping_add(x as u16 + 1);
+            },
+            (0xf, 0x65) => {
+                let start = self.data(x + 1)?;
+                for register in 0..=x {
+                    self.v[register] = self.memory[start + register];
+                }
+                self.i = self.i.wrapping_add(x as u16 + 1);
+            },
+            (0xf, _) => {},
+            _ => unreachable!(),
+        }
+
+        self.pc = next;
+        Ok(())
+    }
+
+    /// The first thing that differs between this machine and `chip8`.
+    pub fn difference(&self, chip8: &Chip8State) -> Option<String> {
+        if self.pc != chip8.pc {
+            return Some(format!("pc is ${:03x}, the core has ${:03x}", self.pc, chip8.pc));
+        }
+        if let Some(register) = (0..16).find(|&register| self.v[register] != chip8.v[register]) {
+            return Some(format!("V{:x} is {:#04x}, the core has {:#04x}", register, self.v[register], chip8.v[register]));
+        }
+        if self.i != chip8.i {
+            return Some(format!("I is ${:03x}, the core has ${:03x}", self.i, chip8.i));
+        }
+        if self.stack != chip8.stack {
+            return Some(format!("the stack is {:x?}, the core has {:x?}", self.stack, chip8.stack));
+        }
+        if self.delay != chip8.delay {
+            return Some(format!("the delay timer is {}, the core has {}", self.delay, chip8.delay));
+        }
+        if self.pressed != chip8.pressed {
+            return Some(format!("keys pressed are {:?}, the core has {:?}", self.pressed, chip8.pressed));
+        }
+        if self.memory[..] != chip8.memory()[..] {
+            let address = (0..4096).find(|&address| self.memory[address] != chip8.memory()[address])?;
+            return Some(format!("${:03x} is {:#04x}, the core has {:#04x}", address, self.memory[address], chip8.memory()[address]));
+        }
+
+        None
+    }
+
+    /// The first pixel that differs, kept apart from `difference` as it is
+    /// by far the slowest thing to compare.
+    pub fn screen_difference(&self, chip8: &Chip8State) -> Option<String> {
+        if self.width() != chip8.screen.width() {
+            return Some(format!("the screen is {} wide, the core's is {}", self.width(), chip8.screen.width()));
+        }
+        for y in 0..self.height() {
+            if let Some(x) = (0..self.width()).find(|&x| self.screen[y][x] != (chip8.screen.pixel(x, y) != 0)) {
+                return Some(format!("pixel {},{} is {}, the core has {}", x, y, self.screen[y][x] as u8, chip8.screen.pixel(x, y)));
+            }
+        }
+
+        None
+    }
+}
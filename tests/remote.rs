@@ -0,0 +1,218 @@
+//! The remote control from the client's side: JSON-RPC lines over a local
+//! socket, answered by `poll` the way a frontend calls it between frames.
+
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::json::Json;
+use chip8_emulator::remote::Server;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+struct Client {
+    server: Server,
+    chip8: Chip8State,
+    stream: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn connect() -> Client {
+        let server = Server::bind("0").unwrap();
+        let stream = TcpStream::connect(("127.0.0.1", server.port().unwrap())).unwrap();
+        Client {server, chip8: Chip8State::builder().build(), stream: BufReader::new(stream)}
+    }
+
+    // sends a line and polls until the server has answered it
+    fn send(&mut self, line: &str) -> Json {
+        writeln!(self.stream.get_mut(), "{}", line).unwrap();
+        while !self.server.poll(&mut self.chip8) {}
+
+        let mut response = String::new();
+        self.stream.read_line(&mut response).unwrap();
+        Json::parse(&response).unwrap()
+    }
+
+    fn call(&mut self, method: &str, params: &str) -> Json {
+        let response = self.send(&format!("{{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"{}\",\"params\":{}}}", method, params));
+        assert_eq!(response.get("id"), Some(&Json::from(7)));
+        response.get("result").cloned().unwrap_or_else(|| panic!("{} failed: {}", method, response))
+    }
+
+    fn error(&mut self, method: &str, params: &str) -> u64 {
+        let response = self.send(&format!("{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"{}\",\"params\":{}}}", method, params));
+        let code = response.get("error").and_then(|error| error.get("code")).unwrap_or_else(|| panic!("{} worked", method));
+        match code {
+            Json::Number(code) => -code as u64,
+            _ => panic!("{} is not a code", code),
+        }
+    }
+}
+
+fn numbers(json: &Json) -> Vec<u64> {
+    json.as_array().unwrap().iter().map(|value| value.as_u64().unwrap()).collect()
+}
+
+#[test]
+fn loads_runs_and_steps() {
+    let mut client = Client::connect();
+    // V0 += 1, draw the 0 from the font and round again, 10 instructions a frame
+    let rom = "7001a000d0151200";
+    assert_eq!(client.call("load_rom", &format!("{{\"rom\":\"{}\"}}", rom)).get("size"), Some(&Json::from(8)));
+
+    let frames = client.call("run_frames", "{\"count\":3}");
+    assert_eq!(frames.get("frame"), Some(&Json::from(3)));
+    let registers = client.call("step", "{\"count\":2}");
+    assert_eq!(registers.get("pc"), Some(&Json::from(0x200)));
+    assert_eq!(client.chip8.instructions, 32);
+
+    let screen = client.call("get_framebuffer", "{}");
+    let rows = screen.get("rows").and_then(Json::as_array).unwrap();
+    assert_eq!(rows.len(), 32);
+    assert!(rows[0].as_str().unwrap().contains('1'));
+}
+
+#[test]
+fn reads_and_writes_memory_and_registers() {
+    let mut client = Client::connect();
+    client.call("load_rom", "{\"rom\":\"6005\"}");
+
+    client.call("write_memory", "{\"address\":512,\"bytes\":[96,9]}");
+    assert_eq!(numbers(&client.call("read_memory", "{\"address\":512,\"length\":2}")), [0x60, 9]);
+    // the write drops what was decoded there before
+    let registers = client.call("step", "{}");
+    assert_eq!(numbers(registers.get("v").unwrap())[0], 9);
+
+    let registers = client.call("set_registers", "{\"v\":[1,2],\"i\":768,\"pc\":512}");
+    assert_eq!(numbers(registers.get("v").unwrap())[..3], [1, 2, 0]);
+    assert_eq!(client.chip8.i, 0x300);
+    assert_eq!(client.chip8.pc, 0x200);
+}
+
+#[test]
+fn keys_stay_down_until_released() {
+    let mut client = Client::connect();
+    // wait for a key and keep it in V0
+    client.call("load_rom", "{\"rom\":\"f00a1200\"}");
+
+    client.call("press_key", "{\"key\":10}");
+    client.call("run_frames", "{}");
+    assert_eq!(client.chip8.v[0], 10);
+    assert!(client.server.keys[10]);
+    client.call("release_key", "{\"key\":10}");
+    assert_eq!(client.server.keys, [false; 16]);
+}
+
+#[test]
+fn steps_see_the_keys_too() {
+    let mut client = Client::connect();
+    client.call("load_rom", "{\"rom\":\"f00a1200\"}");
+
+    client.call("press_key", "{\"key\":3}");
+    let registers = client.call("step", "{}");
+    assert_eq!(numbers(registers.get("v").unwrap())[0], 3);
+    assert_eq!(client.chip8.pc, 0x202);
+}
+
+#[test]
+fn errors_and_notifications() {
+    let mut client = Client::connect();
+
+    assert_eq!(client.send("{nope").get("error").and_then(|error| error.get("code")), Some(&Json::Number(-32700.0)));
+    assert_eq!(client.error("dance", "{}"), 32601);
+    assert_eq!(client.error("press_key", "{\"key\":16}"), 32602);
+    assert_eq!(client.error("read_memory", "{\"address\":4095,\"length\":2}"), 32602);
+    assert_eq!(client.error("load_rom", "{}"), 32602);
+    client.call("load_rom", "{\"rom\":\"00ee\"}");
+    assert_eq!(client.error("step", "{}"), 32000);
+
+    // not requests at all, answered with what id there is
+    for (line, id) in [("5", Json::Null), ("[1]", Json::Null), ("{\"id\":1}", Json::from(1))] {
+        let response = client.send(line);
+        assert_eq!(response.get("id"), Some(&id));
+        assert_eq!(response.get("error").and_then(|error| error.get("code")), Some(&Json::Number(-32600.0)));
+    }
+
+    // no id, no answer, but it still happens
+    writeln!(client.stream.get_mut(), "{{\"jsonrpc\":\"2.0\",\"method\":\"quit\"}}").unwrap();
+    while !client.server.poll(&mut client.chip8) {}
+    assert!(client.server.quit);
+}
+
+#[test]
+fn deep_nesting_is_a_parse_error() {
+    let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Json::parse(&nested(128)).is_ok());
+    assert!(Json::parse(&nested(129)).is_err());
+
+    // would run the parser out of stack if it kept going
+    let mut client = Client::connect();
+    let response = client.send(&"[".repeat(200000));
+    assert_eq!(response.get("error").and_then(|error| error.get("code")), Some(&Json::Number(-32700.0)));
+}
+
+#[test]
+fn a_line_too_long_is_answered_and_dropped() {
+    let mut client = Client::connect();
+    let mut stream = client.stream.get_ref().try_clone().unwrap();
+    // the server only reads between polls, so the sending goes on alongside
+    let sender = thread::spawn(move || stream.write_all(&vec![b' '; 3 << 20]));
+
+    while !client.server.poll(&mut client.chip8) {}
+    let mut response = String::new();
+    client.stream.read_line(&mut response).unwrap();
+    let code = Json::parse(&response).unwrap().get("error").and_then(|error| error.get("code")).cloned();
+    assert_eq!(code, Some(Json::Number(-32600.0)));
+
+    // and that is the last of it
+    client.stream.get_ref().set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut rest = String::new();
+    let ended = client.stream.read_line(&mut rest);
+    assert!(ended.as_ref().map_or_else(|error| error.kind() != ErrorKind::WouldBlock, |&length| length == 0), "{:?}", ended);
+    let _ = sender.join().unwrap();
+}
+
+#[test]
+fn a_client_that_never_reads_is_dropped() {
+    let mut client = Client::connect();
+    let mut stream = client.stream.get_ref().try_clone().unwrap();
+    let requests = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"get_framebuffer\"}\n".repeat(10000);
+    let sender = thread::spawn(move || stream.write_all(requests.as_bytes()));
+
+    // polling never waits on the client, and stops having anything to do once it is gone
+    let mut idle = 0;
+    while idle < 100 {
+        idle = if client.server.poll(&mut client.chip8) {0} else {idle + 1};
+        thread::sleep(Duration::from_millis(1));
+    }
+    let _ = sender.join().unwrap();
+
+    // what it does get is well short of the 10000 responses
+    client.stream.get_ref().set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut received = Vec::new();
+    let ended = client.stream.read_to_end(&mut received);
+    assert!(ended.as_ref().map_or_else(|error| error.kind() != ErrorKind::WouldBlock, |_| true), "{:?}", ended);
+    assert!(received.iter().filter(|&&byte| byte == b'\n').count() < 10000);
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_sockets() {
+    use std::os::unix::net::UnixStream;
+
+    let path = std::env::temp_dir().join(format!("chip8-remote-{}.sock", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut server = Server::bind(path).unwrap();
+    let mut chip8 = Chip8State::builder().build();
+
+    let mut stream = UnixStream::connect(path).unwrap();
+    writeln!(stream, "{{\"jsonrpc\":\"2.0\",\"id\":\"a\",\"method\":\"get_registers\"}}").unwrap();
+    while !server.poll(&mut chip8) {}
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).unwrap();
+    let response = Json::parse(&response).unwrap();
+    assert_eq!(response.get("id"), Some(&Json::from("a")));
+    assert_eq!(response.get("result").and_then(|result| result.get("pc")), Some(&Json::from(0x200)));
+
+    drop(server);
+    assert!(!std::path::Path::new(path).exists());
+}
@@ -0,0 +1,73 @@
+//! The instruction trace as written out, in both formats and through the filters.
+
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::json::Json;
+use chip8_emulator::trace::{TraceFilter, TraceFormat, Tracer};
+use std::fs;
+
+// set V0 and I, call a routine that adds to V0, then loop in place
+const ROM: [u8; 12] = [0x60, 0x05, 0xa3, 0x00, 0x22, 0x08, 0x12, 0x06, 0x70, 0x01, 0x00, 0xee];
+
+fn trace(format: TraceFormat, filter: TraceFilter, frames: u64) -> Vec<String> {
+    let path = std::env::temp_dir().join(format!("chip8-trace-{}-{:?}-{:?}", std::process::id(), format, filter));
+    let path = path.to_str().unwrap();
+    let mut chip8 = Chip8State::builder().rom(&ROM).build();
+    chip8.tracer = Some(Tracer::create(path, format, filter).unwrap());
+    for _ in 0..frames {
+        chip8.run_frame().unwrap();
+    }
+    chip8.tracer.take().unwrap().finish().unwrap();
+
+    let text = fs::read_to_string(path).unwrap();
+    fs::remove_file(path).unwrap();
+    text.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn text_lines_show_what_changed() {
+    let lines = trace(TraceFormat::Text, TraceFilter::default(), 1);
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[..6], [
+        "     0 200 6005 MVI        V0,#$05       V0=0->5",
+        "     0 202 a300 MVI        I,#$300       I=0->300",
+        "     0 204 2208 CALL       $208          SP=0->1 PC->208",
+        "     0 208 7001 ADI        V0,#01        V0=5->6",
+        "     0 20a 00ee RTS                      SP=1->0 PC->206",
+        "     0 206 1206 JUMP       $206          PC->206",
+    ]);
+}
+
+#[test]
+fn json_lines_hold_the_changed_registers() {
+    let lines = trace(TraceFormat::Json, TraceFilter::default(), 1);
+    let steps: Vec<Json> = lines.iter().map(|line| Json::parse(line).unwrap()).collect();
+
+    let call = &steps[2];
+    assert_eq!(call.get("pc"), Some(&Json::from(0x204)));
+    assert_eq!(call.get("opcode"), Some(&Json::from("2208")));
+    assert_eq!(call.get("next_pc"), Some(&Json::from(0x208)));
+    assert_eq!(call.get("changes"), Some(&Json::object(vec![("SP", Json::Array(vec![0.into(), 1.into()]))])));
+
+    let add = &steps[3];
+    assert_eq!(add.get("mnemonic").and_then(Json::as_str), Some("ADI V0,#01"));
+    assert_eq!(add.get("changes"), Some(&Json::object(vec![("V0", Json::Array(vec![5.into(), 6.into()]))])));
+    // a jump to itself changes nothing
+    assert_eq!(steps[5].get("changes"), Some(&Json::Object(Vec::new())));
+}
+
+#[test]
+fn filters_limit_the_trace() {
+    let addresses = |lines: Vec<String>| -> Vec<String> {
+        lines.iter().map(|line| line[..15].trim_start().to_string()).collect()
+    };
+
+    let routine = TraceFilter {pc: Some(0x208..=0x20b), ..TraceFilter::default()};
+    assert_eq!(addresses(trace(TraceFormat::Text, routine, 3)), ["0 208 7001", "0 20a 00ee"]);
+
+    // 2NNN and 0NNN only
+    let calls = TraceFilter {classes: Some(1 << 0x2 | 1 << 0x0), ..TraceFilter::default()};
+    assert_eq!(addresses(trace(TraceFormat::Text, calls, 1)), ["0 204 2208", "0 20a 00ee"]);
+
+    let second_frame = TraceFilter {frames: Some(1..=1), pc: Some(0x206..=0x206), ..TraceFilter::default()};
+    assert_eq!(addresses(trace(TraceFormat::Text, second_frame, 3)), ["1 206 1206"; 10]);
+}
@@ -0,0 +1,77 @@
+//! The watcher catching code that runs from bytes the program wrote or drew.
+
+use chip8_emulator::chip8::Chip8State;
+use chip8_emulator::timing::Timing;
+use chip8_emulator::watch::{Cause, Event, Watcher};
+
+// writes 7005 (V0 += 5) over 20c in the first frame and runs it in the second
+const REWRITES: [u8; 16] = [
+    0x60, 0x70, // V0 = 70
+    0x61, 0x05, // V1 = 05
+    0xa2, 0x0c, // I = 20c
+    0xf1, 0x55, // store V0 and V1 there
+    0x12, 0x0c, // jump to it
+    0x00, 0x00,
+    0x00, 0x00, // written over
+    0x12, 0x0e, // loop here
+];
+
+// draws the byte at 206 and then runs it
+const DRAWS: [u8; 10] = [
+    0xa2, 0x06, // I = 206
+    0xd0, 0x11, // draw a row from there
+    0x12, 0x06, // jump to it
+    0x60, 0x07, // V0 = 7, and the sprite
+    0x12, 0x08, // loop here
+];
+
+fn watched(rom: &[u8], break_on_event: bool) -> Chip8State {
+    let mut chip8 = Chip8State::builder().rom(rom).build();
+    chip8.watcher = Some(Watcher::new(break_on_event));
+    chip8
+}
+
+#[test]
+fn running_written_bytes_is_reported_with_who_wrote_them_and_when() {
+    let mut chip8 = watched(&REWRITES, false);
+    chip8.timing = Timing::Ipf(4);
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.watcher.as_mut().unwrap().take_events(), []);
+    chip8.run_frame().unwrap();
+
+    let events = chip8.watcher.as_mut().unwrap().take_events();
+    assert_eq!(events, [Event {frame: 1, pc: 0x20c, cause: Cause::Written {by: 0x206, frame: 0}}]);
+    assert_eq!(events[0].to_string(), "frame 1: running $20c, which $206 wrote in frame 0");
+    assert_eq!(chip8.v[0], 0x75);
+}
+
+#[test]
+fn running_drawn_bytes_is_reported() {
+    let mut chip8 = watched(&DRAWS, false);
+    chip8.run_frame().unwrap();
+    chip8.run_frame().unwrap();
+
+    // only the first time
+    let events = chip8.watcher.as_mut().unwrap().take_events();
+    assert_eq!(events, [Event {frame: 0, pc: 0x206, cause: Cause::Drawn {by: 0x202}}]);
+    assert_eq!(chip8.v[0], 7);
+}
+
+#[test]
+fn breaking_stops_before_the_instruction_and_runs_it_on_carrying_on() {
+    let mut chip8 = watched(&REWRITES, true);
+    for _ in 0..5 {
+        chip8.step().unwrap();
+    }
+    assert!(!chip8.stopped);
+
+    chip8.step().unwrap();
+    assert!(chip8.stopped);
+    assert_eq!((chip8.pc, chip8.v[0], chip8.instructions), (0x20c, 0x70, 5));
+
+    chip8.stopped = false;
+    chip8.step().unwrap();
+    assert!(!chip8.stopped);
+    assert_eq!((chip8.pc, chip8.v[0], chip8.instructions), (0x20e, 0x75, 6));
+    assert_eq!(chip8.watcher.as_mut().unwrap().take_events().len(), 1);
+}